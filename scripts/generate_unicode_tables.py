@@ -0,0 +1,254 @@
+#!/usr/bin/env python3
+"""Generates src/unicode_tables.rs from Python's bundled Unicode database.
+
+Script data is not part of unicodedata, so it is taken from the third-party
+`regex` package and restricted to code points assigned in the bundled version.
+
+Usage: scripts/generate_unicode_tables.py > src/unicode_tables.rs && cargo fmt
+"""
+
+import sys
+import unicodedata
+
+import regex
+from regex import _regex_core
+
+MAX_CODEPOINT = 0x10FFFF
+
+# Turkic dotted and dotless i only fold with locale-specific (status T) mappings.
+TURKIC_I = {"\u0130", "\u0131"}
+
+
+def is_char(cp):
+    return not 0xD800 <= cp <= 0xDFFF
+
+
+def simple_mappings(ch):
+    if ch in TURKIC_I:
+        return
+
+    for mapped in (ch.lower(), ch.upper(), ch.title()):
+        if len(mapped) == 1 and mapped != ch:
+            yield mapped
+
+
+def case_orbits():
+    parent = {}
+
+    def find(ch):
+        while parent.get(ch, ch) != ch:
+            ch = parent[ch]
+        return ch
+
+    for cp in range(MAX_CODEPOINT + 1):
+        if not is_char(cp):
+            continue
+
+        ch = chr(cp)
+
+        for mapped in simple_mappings(ch):
+            a, b = find(ch), find(mapped)
+            if a != b:
+                parent[max(a, b)] = min(a, b)
+
+    orbits = {}
+
+    for ch in parent:
+        orbits.setdefault(find(ch), set()).add(ch)
+
+    for root, orbit in orbits.items():
+        orbit.add(root)
+
+    return {ch: sorted(orbit - {ch}) for orbit in orbits.values() for ch in orbit}
+
+
+CATEGORY_NAMES = {
+    "Lu": ["Uppercase_Letter"],
+    "Ll": ["Lowercase_Letter"],
+    "Lt": ["Titlecase_Letter"],
+    "Lm": ["Modifier_Letter"],
+    "Lo": ["Other_Letter"],
+    "Mn": ["Nonspacing_Mark"],
+    "Mc": ["Spacing_Mark"],
+    "Me": ["Enclosing_Mark"],
+    "Nd": ["Decimal_Number", "digit"],
+    "Nl": ["Letter_Number"],
+    "No": ["Other_Number"],
+    "Pc": ["Connector_Punctuation"],
+    "Pd": ["Dash_Punctuation"],
+    "Ps": ["Open_Punctuation"],
+    "Pe": ["Close_Punctuation"],
+    "Pi": ["Initial_Punctuation"],
+    "Pf": ["Final_Punctuation"],
+    "Po": ["Other_Punctuation"],
+    "Sm": ["Math_Symbol"],
+    "Sc": ["Currency_Symbol"],
+    "Sk": ["Modifier_Symbol"],
+    "So": ["Other_Symbol"],
+    "Zs": ["Space_Separator"],
+    "Zl": ["Line_Separator"],
+    "Zp": ["Paragraph_Separator"],
+    "Cc": ["Control", "cntrl"],
+    "Cf": ["Format"],
+    "Co": ["Private_Use"],
+    "Cn": ["Unassigned"],
+}
+
+CATEGORY_GROUPS = {
+    "L": (["Letter"], ["Lu", "Ll", "Lt", "Lm", "Lo"]),
+    "LC": (["Cased_Letter"], ["Lu", "Ll", "Lt"]),
+    "M": (["Mark", "Combining_Mark"], ["Mn", "Mc", "Me"]),
+    "N": (["Number"], ["Nd", "Nl", "No"]),
+    "P": (["Punctuation", "punct"], ["Pc", "Pd", "Ps", "Pe", "Pi", "Pf", "Po"]),
+    "S": (["Symbol"], ["Sm", "Sc", "Sk", "So"]),
+    "Z": (["Separator"], ["Zs", "Zl", "Zp"]),
+    "C": (["Other"], ["Cc", "Cf", "Co", "Cn"]),
+}
+
+
+def loose_name(name):
+    return name.replace("_", "").replace("-", "").replace(" ", "").lower()
+
+
+def to_ranges(cps):
+    ranges = []
+
+    for cp in sorted(cps):
+        if ranges and ranges[-1][1] + 1 == cp:
+            ranges[-1][1] = cp
+        else:
+            ranges.append([cp, cp])
+
+    return ranges
+
+
+def is_assigned(cp):
+    return unicodedata.category(chr(cp)) != "Cn"
+
+
+def general_categories():
+    members = {}
+
+    for cp in range(MAX_CODEPOINT + 1):
+        if is_char(cp):
+            members.setdefault(unicodedata.category(chr(cp)), set()).add(cp)
+
+    tables = {}
+
+    for code, aliases in CATEGORY_NAMES.items():
+        tables[code] = ([code] + aliases, members.get(code, set()))
+
+    for code, (aliases, parts) in CATEGORY_GROUPS.items():
+        tables[code] = ([code] + aliases, set().union(*(tables[p][1] for p in parts)))
+
+    return tables
+
+
+def scripts():
+    text = "".join(chr(cp) for cp in range(MAX_CODEPOINT + 1) if is_char(cp))
+    names = {}
+
+    for name, value in _regex_core.PROPERTIES["SCRIPT"][1].items():
+        names.setdefault(value, []).append(name)
+
+    tables = {}
+
+    for aliases in names.values():
+        main = max(aliases, key=len)
+        pattern = regex.compile(r"\p{Script=%s}+" % main)
+        cps = set()
+
+        for match in pattern.finditer(text):
+            for i in range(*match.span()):
+                cp = ord(text[i])
+
+                if is_assigned(cp):
+                    cps.add(cp)
+
+        if cps:
+            tables[main] = (aliases, cps)
+
+    return tables
+
+
+def binary_property(name):
+    pattern = regex.compile(r"\p{%s}" % name)
+    return {cp for cp in range(MAX_CODEPOINT + 1)
+            if is_char(cp) and is_assigned(cp) and pattern.match(chr(cp))}
+
+
+def perl_classes(categories):
+    digit = categories["Nd"][1]
+    word = set().union(
+        binary_property("Alphabetic"),
+        categories["M"][1],
+        digit,
+        categories["Pc"][1],
+        binary_property("Join_Control"),
+    )
+
+    return {
+        "PERL_DIGIT": digit,
+        "PERL_SPACE": binary_property("White_Space"),
+        "PERL_WORD": word,
+    }
+
+
+def rust_char(ch):
+    return "'\\u{%X}'" % ord(ch)
+
+
+def write_case_folding(out):
+    table = case_orbits()
+
+    out.write("pub const CASE_FOLDING: &[(char, &[char])] = &[\n")
+
+    for ch in sorted(table):
+        others = ", ".join(rust_char(other) for other in table[ch])
+        out.write("    (%s, &[%s]),\n" % (rust_char(ch), others))
+
+    out.write("];\n")
+
+
+def write_property_tables(out, prefix, const, tables):
+    entries = []
+
+    for name, (aliases, cps) in sorted(tables.items()):
+        ident = "%s_%s" % (prefix, loose_name(name).upper())
+
+        write_ranges(out, ident, cps)
+        entries.extend((loose_name(alias), ident) for alias in aliases)
+
+    out.write("\npub const %s: &[(&str, &[(char, char)])] = &[\n" % const)
+
+    for name, ident in sorted(set(entries)):
+        out.write('    ("%s", %s),\n' % (name, ident))
+
+    out.write("];\n")
+
+
+def write_ranges(out, ident, cps, public=False):
+    out.write("\n%sconst %s: &[(char, char)] = &[\n" % ("pub " if public else "", ident))
+
+    for lower, upper in to_ranges(cps):
+        out.write("    (%s, %s),\n" % (rust_char(chr(lower)), rust_char(chr(upper))))
+
+    out.write("];\n")
+
+
+def main():
+    out = sys.stdout
+    out.write("// Generated by scripts/generate_unicode_tables.py from Unicode %s.\n"
+              % unicodedata.unidata_version)
+    out.write("// Do not edit by hand.\n\n")
+    write_case_folding(out)
+    categories = general_categories()
+    write_property_tables(out, "GC", "GENERAL_CATEGORIES", categories)
+    write_property_tables(out, "SC", "SCRIPTS", scripts())
+
+    for ident, cps in sorted(perl_classes(categories).items()):
+        write_ranges(out, ident, cps, public=True)
+
+
+if __name__ == "__main__":
+    main()
@@ -1,3 +1,7 @@
+use crate::{
+    interval_set::IntervalSet,
+    unicode::{fold_set, in_ranges},
+};
 use std::fmt;
 
 #[derive(Debug, PartialEq)]
@@ -9,10 +13,15 @@ pub enum Node {
     Plus(Box<Node>),
     Optional(Box<Node>),
     Range { inner: Box<Node>, range: Range },
+    LazyStar(Box<Node>),
+    LazyPlus(Box<Node>),
+    LazyOptional(Box<Node>),
+    LazyRange { inner: Box<Node>, range: Range },
     Group(Group),
     Wildcard,
     Character(char),
     CharacterClass(CharacterClass),
+    Assertion(Assertion),
 }
 
 impl Node {
@@ -43,12 +52,22 @@ impl Node {
         }
     }
 
+    pub fn lazy(self) -> Self {
+        match self {
+            Self::Star(inner) => Self::LazyStar(inner),
+            Self::Plus(inner) => Self::LazyPlus(inner),
+            Self::Optional(inner) => Self::LazyOptional(inner),
+            Self::Range { inner, range } => Self::LazyRange { inner, range },
+            node => node,
+        }
+    }
+
     pub fn group(inner: Node, is_capturing: bool, name: Option<&str>) -> Self {
         Self::Group(Group::new(inner, is_capturing, name.map(str::to_string)))
     }
 
     pub fn class(negate: bool, members: Vec<ClassMember>) -> Self {
-        Self::CharacterClass(CharacterClass { negate, members })
+        Self::CharacterClass(CharacterClass::new(negate, members))
     }
 }
 
@@ -81,10 +100,62 @@ impl Range {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnicodeProperty {
+    pub name: &'static str,
+    pub negate: bool,
+    pub ranges: &'static [(char, char)],
+}
+
+impl UnicodeProperty {
+    pub fn contains(&self, input: &char) -> bool {
+        self.negate ^ in_ranges(self.ranges, *input)
+    }
+
+    pub fn to_set(self, fold: bool) -> IntervalSet {
+        let set = fold_leaf(IntervalSet::new(self.ranges.iter().copied()), fold);
+
+        match self.negate {
+            true => set.complement(),
+            false => set,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ClassMember {
     Atom(char),
     Range(char, char),
+    Property(UnicodeProperty),
+    Class(CharacterClass),
+    Intersection(CharacterClass, CharacterClass),
+    Difference(CharacterClass, CharacterClass),
+}
+
+impl ClassMember {
+    pub fn contains(&self, input: &char) -> bool {
+        match self {
+            ClassMember::Atom(ch) => input == ch,
+            ClassMember::Range(lower, upper) => lower <= input && upper >= input,
+            ClassMember::Property(property) => property.contains(input),
+            ClassMember::Class(class) => class.contains(input),
+            ClassMember::Intersection(lhs, rhs) => lhs.contains(input) && rhs.contains(input),
+            ClassMember::Difference(lhs, rhs) => lhs.contains(input) && !rhs.contains(input),
+        }
+    }
+
+    pub fn to_set(&self, fold: bool) -> IntervalSet {
+        match self {
+            ClassMember::Atom(ch) => fold_leaf(IntervalSet::new([(*ch, *ch)]), fold),
+            ClassMember::Range(lower, upper) => {
+                fold_leaf(IntervalSet::new([(*lower, *upper)]), fold)
+            }
+            ClassMember::Property(property) => property.to_set(fold),
+            ClassMember::Class(class) => class.set.clone(),
+            ClassMember::Intersection(lhs, rhs) => lhs.set.intersection(&rhs.set),
+            ClassMember::Difference(lhs, rhs) => lhs.set.difference(&rhs.set),
+        }
+    }
 }
 
 impl fmt::Display for ClassMember {
@@ -92,6 +163,13 @@ impl fmt::Display for ClassMember {
         match self {
             ClassMember::Atom(ch) => write!(f, "{}", format_char(ch)),
             ClassMember::Range(lower, upper) => write!(f, "{lower}-{upper}"),
+            ClassMember::Property(UnicodeProperty { name, negate, .. }) => match negate {
+                true => write!(f, r"\\P{{{name}}}"),
+                false => write!(f, r"\\p{{{name}}}"),
+            },
+            ClassMember::Class(class) => write!(f, "{class}"),
+            ClassMember::Intersection(lhs, rhs) => write!(f, "{lhs}&&{rhs}"),
+            ClassMember::Difference(lhs, rhs) => write!(f, "{lhs}--{rhs}"),
         }
     }
 }
@@ -100,6 +178,36 @@ impl fmt::Display for ClassMember {
 pub struct CharacterClass {
     pub negate: bool,
     pub members: Vec<ClassMember>,
+    set: IntervalSet,
+}
+
+impl CharacterClass {
+    pub fn new(negate: bool, members: Vec<ClassMember>) -> Self {
+        Self::with_case_folding(negate, members, false)
+    }
+
+    pub fn with_case_folding(negate: bool, members: Vec<ClassMember>, fold: bool) -> Self {
+        let sets: Vec<_> = members.iter().map(|member| member.to_set(fold)).collect();
+        let set = IntervalSet::union(&sets);
+        let set = match negate {
+            true => set.complement(),
+            false => set,
+        };
+
+        Self {
+            negate,
+            members,
+            set,
+        }
+    }
+
+    pub fn set(&self) -> &IntervalSet {
+        &self.set
+    }
+
+    pub fn contains(&self, input: &char) -> bool {
+        self.set.contains(*input)
+    }
 }
 
 impl fmt::Display for CharacterClass {
@@ -116,6 +224,52 @@ impl fmt::Display for CharacterClass {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Assertion {
+    StartOfInput,
+    EndOfInput,
+    StartOfLine,
+    EndOfLine,
+    WordBoundary,
+    NotWordBoundary,
+    AsciiWordBoundary,
+    AsciiNotWordBoundary,
+}
+
+impl Assertion {
+    pub fn is_word_boundary(&self) -> bool {
+        matches!(
+            self,
+            Assertion::WordBoundary
+                | Assertion::NotWordBoundary
+                | Assertion::AsciiWordBoundary
+                | Assertion::AsciiNotWordBoundary
+        )
+    }
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Assertion::StartOfInput => write!(f, "^"),
+            Assertion::EndOfInput => write!(f, "$"),
+            Assertion::StartOfLine => write!(f, "(?m)^"),
+            Assertion::EndOfLine => write!(f, "(?m)$"),
+            Assertion::WordBoundary => write!(f, r"\\b"),
+            Assertion::NotWordBoundary => write!(f, r"\\B"),
+            Assertion::AsciiWordBoundary => write!(f, r"(?-u)\\b"),
+            Assertion::AsciiNotWordBoundary => write!(f, r"(?-u)\\B"),
+        }
+    }
+}
+
+fn fold_leaf(set: IntervalSet, fold: bool) -> IntervalSet {
+    match fold {
+        true => fold_set(&set),
+        false => set,
+    }
+}
+
 fn format_char(ch: &char) -> String {
     match ch {
         ' ' => r"\\s".to_owned(),
@@ -0,0 +1,97 @@
+use crate::{
+    dfa::DEFAULT_STATE_LIMIT,
+    error::{Error, ParsingError},
+    nfa::{Nfa, DEFAULT_SIZE_LIMIT},
+    parser::{parse_regex_recovering, parse_regex_with_config, ParserConfig},
+    pikevm::MatchSemantics,
+    regex::Regex,
+};
+
+#[derive(Debug, Clone)]
+pub struct RegexBuilder<'a> {
+    pattern: &'a str,
+    config: ParserConfig,
+    size_limit: usize,
+    semantics: MatchSemantics,
+    full_dfa: bool,
+    dfa_state_limit: usize,
+}
+
+impl<'a> RegexBuilder<'a> {
+    pub fn new(pattern: &'a str) -> Self {
+        Self {
+            pattern,
+            config: ParserConfig::default(),
+            size_limit: DEFAULT_SIZE_LIMIT,
+            semantics: MatchSemantics::default(),
+            full_dfa: false,
+            dfa_state_limit: DEFAULT_STATE_LIMIT,
+        }
+    }
+
+    pub fn multiline(mut self, value: bool) -> Self {
+        self.config.flags.multiline = value;
+        self
+    }
+
+    pub fn case_insensitive(mut self, value: bool) -> Self {
+        self.config.flags.case_insensitive = value;
+        self
+    }
+
+    pub fn dot_matches_newline(mut self, value: bool) -> Self {
+        self.config.flags.dot_matches_newline = value;
+        self
+    }
+
+    pub fn ignore_whitespace(mut self, value: bool) -> Self {
+        self.config.flags.ignore_whitespace = value;
+        self
+    }
+
+    pub fn unicode(mut self, value: bool) -> Self {
+        self.config.flags.unicode = value;
+        self
+    }
+
+    pub fn size_limit(mut self, limit: usize) -> Self {
+        self.size_limit = limit;
+        self
+    }
+
+    pub fn nest_limit(mut self, limit: usize) -> Self {
+        self.config.nest_limit = limit;
+        self
+    }
+
+    pub fn legacy_non_capturing(mut self, value: bool) -> Self {
+        self.config.legacy_non_capturing = value;
+        self
+    }
+
+    pub fn match_semantics(mut self, semantics: MatchSemantics) -> Self {
+        self.semantics = semantics;
+        self
+    }
+
+    pub fn full_dfa(mut self, value: bool) -> Self {
+        self.full_dfa = value;
+        self
+    }
+
+    pub fn dfa_state_limit(mut self, limit: usize) -> Self {
+        self.dfa_state_limit = limit;
+        self
+    }
+
+    pub fn diagnostics(&self) -> Vec<ParsingError> {
+        parse_regex_recovering(self.pattern, self.config).1
+    }
+
+    pub fn build(&self) -> Result<Regex, Error> {
+        let ast = parse_regex_with_config(self.pattern, self.config)?;
+        let nfa = Nfa::compile(ast, self.size_limit)?;
+        let dfa_state_limit = self.full_dfa.then_some(self.dfa_state_limit);
+        Ok(Regex::from_nfa(nfa, self.semantics, dfa_state_limit))
+    }
+}
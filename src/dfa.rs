@@ -0,0 +1,546 @@
+use crate::{
+    nfa::{CharKind, Context, Nfa, StateId, Transition, START},
+    parser::WORD_MEMBERS,
+    pikevm::MatchSemantics,
+    program::SparseSet,
+    unicode_tables::PERL_WORD,
+};
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+
+pub const DEFAULT_STATE_LIMIT: usize = 10_000;
+const WORK_PER_STATE: usize = 1_000;
+
+pub type DfaStateId = usize;
+
+const CHAR_KINDS: [CharKind; 5] = [
+    CharKind::End,
+    CharKind::Newline,
+    CharKind::AsciiWord,
+    CharKind::Word,
+    CharKind::Other,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DfaState {
+    pub states: Vec<StateId>,
+    pub prev: CharKind,
+    pub matched: bool,
+}
+
+#[derive(Debug)]
+pub struct Determinizer<'a> {
+    nfa: &'a Nfa,
+    semantics: MatchSemantics,
+    anchored: bool,
+    closures: HashMap<(StateId, Context), Vec<StateId>>,
+    closure: SparseSet,
+    consuming: Vec<&'a Transition>,
+    targets: SparseSet,
+}
+
+impl<'a> Determinizer<'a> {
+    pub fn new(nfa: &'a Nfa, semantics: MatchSemantics, anchored: bool) -> Self {
+        Self {
+            nfa,
+            semantics,
+            anchored,
+            closures: HashMap::new(),
+            closure: SparseSet::new(nfa.state_count),
+            consuming: Vec::new(),
+            targets: SparseSet::new(nfa.state_count),
+        }
+    }
+
+    pub fn start(&self, prev: CharKind) -> DfaState {
+        DfaState {
+            states: vec![START],
+            prev,
+            matched: false,
+        }
+    }
+
+    pub fn next(&mut self, state: &DfaState, ch: char) -> (DfaState, bool) {
+        let is_match = self.closure(state, Some(ch).into());
+
+        (self.step(state, ch, is_match), is_match)
+    }
+
+    pub fn eoi_match(&mut self, state: &DfaState) -> bool {
+        self.closure(state, CharKind::End)
+    }
+
+    pub fn is_dead(&self, state: &DfaState) -> bool {
+        (self.anchored || state.matched) && state.states.is_empty()
+    }
+
+    /// Computes the closure of `state` before a character of kind `next`,
+    /// returning whether it matches. `step` reads from this closure.
+    pub fn closure(&mut self, state: &DfaState, next: CharKind) -> bool {
+        let context = Context {
+            prev: state.prev,
+            next,
+        };
+        let seed = match self.anchored || state.matched {
+            true => None,
+            false => Some(START),
+        };
+        let nfa = self.nfa;
+        let mut is_match = false;
+
+        self.closure.clear();
+        self.consuming.clear();
+
+        for start in state.states.iter().copied().chain(seed) {
+            let closure = self
+                .closures
+                .entry((start, context))
+                .or_insert_with(|| nfa.epsilon_closure(start, &context));
+
+            for &state in closure.iter() {
+                if !self.closure.insert(state) {
+                    continue;
+                }
+
+                self.consuming.extend(nfa.consuming_transitions(state));
+
+                if nfa.is_accepting(state) {
+                    is_match = true;
+
+                    if self.semantics == MatchSemantics::LeftmostFirst {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        is_match
+    }
+
+    pub fn step(&mut self, state: &DfaState, ch: char, is_match: bool) -> DfaState {
+        self.targets.clear();
+
+        for transition in &self.consuming {
+            if transition.accept(&ch) {
+                self.targets.insert(transition.end);
+            }
+        }
+
+        DfaState {
+            states: self.targets.iter().collect(),
+            prev: Some(ch).into(),
+            matched: state.matched || is_match,
+        }
+    }
+
+    /// The cost of a `step` from the current closure.
+    pub fn work(&self) -> usize {
+        self.consuming.len() + 1
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alphabet {
+    starts: Vec<u32>,
+}
+
+impl Alphabet {
+    pub fn new(nfa: &Nfa) -> Self {
+        let word_ranges = match nfa.has_word_boundary() {
+            true => WORD_MEMBERS
+                .iter()
+                .flat_map(|member| member.to_set(false).ranges().to_vec())
+                .chain(PERL_WORD.iter().copied())
+                .collect(),
+            false => Vec::new(),
+        };
+        let ranges = nfa
+            .transitions
+            .values()
+            .flatten()
+            .flat_map(|t| t.kind.ranges())
+            .chain(word_ranges)
+            .chain([('\n', '\n')]);
+        let mut starts = vec![0, 0xD800, 0xE000];
+
+        for (lower, upper) in ranges {
+            starts.push(lower as u32);
+
+            if upper < char::MAX {
+                starts.push(upper as u32 + 1);
+            }
+        }
+
+        starts.sort_unstable();
+        starts.dedup();
+
+        Self { starts }
+    }
+
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    pub fn class_of(&self, ch: char) -> usize {
+        self.starts.partition_point(|&start| start <= ch as u32) - 1
+    }
+
+    pub fn representative(&self, class: usize) -> Option<char> {
+        char::from_u32(self.starts[class])
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Dfa {
+    alphabet: Alphabet,
+    transitions: Vec<DfaStateId>,
+    matches: Vec<bool>,
+    eoi_matches: Vec<bool>,
+    starts: [DfaStateId; 5],
+    dead: DfaStateId,
+}
+
+impl Dfa {
+    pub fn new(
+        nfa: &Nfa,
+        semantics: MatchSemantics,
+        anchored: bool,
+        state_limit: usize,
+    ) -> Option<Self> {
+        let alphabet = Alphabet::new(nfa);
+        let mut determinizer = Determinizer::new(nfa, semantics, anchored);
+        let mut queue = VecDeque::new();
+        let mut ids = HashMap::new();
+        let mut intern = |state: DfaState, queue: &mut VecDeque<DfaState>| {
+            let id = ids.len();
+
+            match ids.entry(state) {
+                Entry::Occupied(entry) => *entry.get(),
+                Entry::Vacant(entry) => {
+                    queue.push_back(entry.key().clone());
+                    *entry.insert(id)
+                }
+            }
+        };
+
+        let dead_state = DfaState {
+            states: Vec::new(),
+            prev: CharKind::End,
+            matched: true,
+        };
+        let dead = intern(dead_state, &mut queue);
+        let starts = CHAR_KINDS.map(|kind| intern(determinizer.start(kind), &mut queue));
+        let mut kinds = CHAR_KINDS.map(|kind| (kind, Vec::new()));
+        let mut transitions = Vec::new();
+        let mut matches = Vec::new();
+        let mut eoi_matches = Vec::new();
+        let mut work = 0;
+
+        for class in 0..alphabet.len() {
+            if let Some(ch) = alphabet.representative(class) {
+                let kind = CharKind::from(Some(ch));
+                let index = CHAR_KINDS.iter().position(|&k| k == kind);
+
+                kinds[index.unwrap_or_default()].1.push((class, ch));
+            }
+        }
+
+        while let Some(state) = queue.pop_front() {
+            let id = eoi_matches.len();
+            let row = transitions.len();
+
+            if id + queue.len() >= state_limit {
+                return None;
+            }
+
+            transitions.resize(row + alphabet.len(), dead);
+            matches.resize(row + alphabet.len(), false);
+
+            for (kind, classes) in kinds.iter().filter(|_| id != dead) {
+                if classes.is_empty() {
+                    continue;
+                }
+
+                let is_match = determinizer.closure(&state, *kind);
+
+                for &(class, ch) in classes {
+                    work += determinizer.work();
+
+                    if work > state_limit * WORK_PER_STATE {
+                        return None;
+                    }
+
+                    let next = determinizer.step(&state, ch, is_match);
+
+                    transitions[row + class] = match determinizer.is_dead(&next) {
+                        true => dead,
+                        false => intern(next, &mut queue),
+                    };
+                    matches[row + class] = is_match;
+                }
+            }
+
+            eoi_matches.push(id != dead && determinizer.eoi_match(&state));
+        }
+
+        Some(Self {
+            alphabet,
+            transitions,
+            matches,
+            eoi_matches,
+            starts,
+            dead,
+        })
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.eoi_matches.len()
+    }
+
+    pub fn start_state(&self, prev: CharKind) -> DfaStateId {
+        let index = CHAR_KINDS.iter().position(|&kind| kind == prev);
+        self.starts[index.unwrap_or_default()]
+    }
+
+    pub fn is_dead(&self, state: DfaStateId) -> bool {
+        state == self.dead
+    }
+
+    pub fn minimize(self) -> Self {
+        let classes = self.alphabet.len();
+        let state_count = self.state_count();
+        let mut inverse = vec![vec![Vec::new(); state_count]; classes];
+        let mut signatures = HashMap::new();
+        let mut blocks: Vec<Vec<DfaStateId>> = Vec::new();
+        let mut block_of = Vec::with_capacity(state_count);
+
+        for state in 0..state_count {
+            let row = state * classes..(state + 1) * classes;
+
+            for (class, &next) in self.transitions[row.clone()].iter().enumerate() {
+                inverse[class][next].push(state);
+            }
+
+            let signature = (self.eoi_matches[state], &self.matches[row]);
+            let block = *signatures.entry(signature).or_insert_with(|| {
+                blocks.push(Vec::new());
+                blocks.len() - 1
+            });
+
+            blocks[block].push(state);
+            block_of.push(block);
+        }
+
+        let mut worklist: VecDeque<_> = (0..blocks.len())
+            .flat_map(|block| (0..classes).map(move |class| (block, class)))
+            .collect();
+        let mut pending: HashSet<_> = worklist.iter().copied().collect();
+
+        while let Some((splitter, class)) = worklist.pop_front() {
+            pending.remove(&(splitter, class));
+
+            let mut touched: HashMap<usize, Vec<DfaStateId>> = HashMap::new();
+            let predecessors = blocks[splitter]
+                .iter()
+                .flat_map(|&state| &inverse[class][state]);
+
+            for &state in predecessors {
+                touched.entry(block_of[state]).or_default().push(state);
+            }
+
+            for (block, mut inside) in touched {
+                inside.sort_unstable();
+                inside.dedup();
+
+                if inside.len() == blocks[block].len() {
+                    continue;
+                }
+
+                let new_block = blocks.len();
+
+                blocks[block].retain(|state| inside.binary_search(state).is_err());
+
+                for &state in &inside {
+                    block_of[state] = new_block;
+                }
+
+                let smaller = match inside.len() < blocks[block].len() {
+                    true => new_block,
+                    false => block,
+                };
+
+                blocks.push(inside);
+
+                for class in 0..classes {
+                    if pending.contains(&(block, class)) {
+                        pending.insert((new_block, class));
+                        worklist.push_back((new_block, class));
+                    } else if pending.insert((smaller, class)) {
+                        worklist.push_back((smaller, class));
+                    }
+                }
+            }
+        }
+
+        let mut transitions = Vec::with_capacity(blocks.len() * classes);
+        let mut matches = Vec::with_capacity(blocks.len() * classes);
+        let mut eoi_matches = Vec::with_capacity(blocks.len());
+
+        for block in &blocks {
+            let state = block[0];
+            let row = state * classes..(state + 1) * classes;
+
+            transitions.extend(self.transitions[row.clone()].iter().map(|&s| block_of[s]));
+            matches.extend_from_slice(&self.matches[row]);
+            eoi_matches.push(self.eoi_matches[state]);
+        }
+
+        Self {
+            alphabet: self.alphabet,
+            transitions,
+            matches,
+            eoi_matches,
+            starts: self.starts.map(|state| block_of[state]),
+            dead: block_of[self.dead],
+        }
+    }
+
+    pub fn is_match(&self, input: &str, start: usize) -> bool {
+        self.search(input, start, true).is_some()
+    }
+
+    pub fn find_end(&self, input: &str, start: usize) -> Option<usize> {
+        self.search(input, start, false)
+    }
+
+    fn search(&self, input: &str, start: usize, earliest: bool) -> Option<usize> {
+        let mut state = self.start_state(input[..start].chars().next_back().into());
+        let mut end = None;
+
+        for (i, ch) in input[start..].char_indices() {
+            let index = state * self.alphabet.len() + self.alphabet.class_of(ch);
+
+            if self.matches[index] {
+                end = Some(start + i);
+
+                if earliest {
+                    return end;
+                }
+            }
+
+            state = self.transitions[index];
+
+            if self.is_dead(state) {
+                return end;
+            }
+        }
+
+        if self.eoi_matches[state] {
+            end = Some(input.len());
+        }
+
+        end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_regex;
+    use std::time::{Duration, Instant};
+
+    fn to_dfa(regex: &str, anchored: bool) -> Dfa {
+        let nfa = Nfa::from(parse_regex(regex).unwrap());
+        Dfa::new(
+            &nfa,
+            MatchSemantics::LeftmostFirst,
+            anchored,
+            DEFAULT_STATE_LIMIT,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_alphabet() {
+        let nfa = Nfa::from(parse_regex("[a-c]x").unwrap());
+        let alphabet = Alphabet::new(&nfa);
+
+        assert_eq!(alphabet.class_of('a'), alphabet.class_of('c'));
+        assert_ne!(alphabet.class_of('c'), alphabet.class_of('d'));
+        assert_ne!(alphabet.class_of('w'), alphabet.class_of('x'));
+        assert_eq!(alphabet.representative(alphabet.class_of('b')), Some('a'));
+    }
+
+    #[test]
+    fn test_find_end() {
+        let dfa = to_dfa("a|ab", true);
+
+        assert_eq!(dfa.search("ab", 0, false), Some(1));
+
+        let dfa = to_dfa(r"(foo|bar)+\b", true);
+
+        assert_eq!(dfa.search("foobarfoo baz", 0, false), Some(9));
+        assert_eq!(dfa.search("foobarf", 0, false), None);
+
+        let dfa = to_dfa("b+$", false);
+
+        assert!(dfa.is_match("abbb", 0));
+        assert!(!dfa.is_match("abba", 0));
+
+        let dfa = to_dfa("ab|b+", false);
+
+        assert_eq!(dfa.find_end("xbbab", 0), Some(3));
+        assert_eq!(dfa.find_end("xabbb", 0), Some(3));
+        assert_eq!(dfa.find_end("xyz", 0), None);
+    }
+
+    #[test]
+    fn test_minimize() {
+        let dfa = to_dfa("(a|b)*abb", false);
+        let minimized = dfa.clone().minimize();
+
+        assert!(minimized.state_count() < dfa.state_count());
+
+        for input in ["abb", "babb", "aabbabb", "ab", "abba", ""] {
+            assert_eq!(dfa.is_match(input, 0), minimized.is_match(input, 0));
+            assert_eq!(
+                dfa.search(input, 0, false),
+                minimized.search(input, 0, false)
+            );
+        }
+
+        let minimized = to_dfa("(a|b)*abb$", false).minimize();
+
+        assert!(minimized.is_match("aababb", 0));
+        assert!(!minimized.is_match("aababba", 0));
+    }
+
+    #[test]
+    fn test_state_limit() {
+        let nfa = Nfa::from(parse_regex("(a|b)*a(a|b){8}").unwrap());
+
+        assert!(Dfa::new(&nfa, MatchSemantics::LeftmostFirst, false, 64).is_none());
+        assert!(Dfa::new(&nfa, MatchSemantics::LeftmostFirst, false, 4096).is_some());
+    }
+
+    #[test]
+    fn test_work_limit() {
+        let pattern = [
+            r"b.|(?:(?:(?:\B|(?m)^{0}a{0,2}.)?|\b[a-c]{1,3}$|(?m:$)\Bc)",
+            r"|(?:\b|(?m)^a{0,3}\B.)?(?:(?:\B|(?m)^b{0,2}.)?|\b[b-d]{1,3}$|(?m:^)\bd)",
+            r"|(?:\B.|\b(?m:$)){0,3}|(?:(?:\B|(?m)^a{0,2}.)?\b.{0,4}\B){1,3}",
+            r"(?:\b|\B|$|^){2}.{2,5})c{2,}",
+        ]
+        .concat();
+        let nfa = Nfa::from(parse_regex(&pattern).unwrap());
+        let start = Instant::now();
+
+        assert!(Dfa::new(
+            &nfa,
+            MatchSemantics::LeftmostFirst,
+            false,
+            DEFAULT_STATE_LIMIT
+        )
+        .is_none());
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
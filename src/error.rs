@@ -4,14 +4,79 @@ use thiserror::Error;
 pub enum Error {
     #[error("{0}")]
     ParsingError(#[from] ParsingError),
+    #[error("Compiled NFA exceeds the size limit of {0} states")]
+    NfaSizeLimitExceeded(usize),
 }
 
-#[derive(Debug, Error)]
-pub enum ParsingError {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("{kind} at position {position}")]
+pub struct ParsingError {
+    pub kind: ParsingErrorKind,
+    pub span: Span,
+    pub position: usize,
+}
+
+impl ParsingError {
+    pub fn new(kind: ParsingErrorKind, span: Span, pattern: &str) -> Self {
+        Self {
+            kind,
+            span,
+            position: char_offset(pattern, span.start),
+        }
+    }
+
+    pub fn char_span(&self, pattern: &str) -> Span {
+        let count = |offset: usize| char_offset(pattern, offset);
+        Span::new(count(self.span.start), count(self.span.end))
+    }
+
+    pub fn render(&self, pattern: &str) -> String {
+        let start = self.span.start.min(pattern.len());
+        let line_start = pattern[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = pattern[start..]
+            .find('\n')
+            .map_or(pattern.len(), |i| start + i);
+        let end = self.span.end.clamp(start, line_end);
+        let line_number = (pattern[..start].matches('\n').count() + 1).to_string();
+        let column = pattern[line_start..start].chars().count();
+        let width = pattern[start..end].chars().count().max(1);
+        let gutter = " ".repeat(line_number.len());
+
+        format!(
+            "error: {}\n{gutter}--> {line_number}:{}\n{gutter} |\n{line_number} | {}\n{gutter} | {}{}\n",
+            self.kind,
+            column + 1,
+            &pattern[line_start..line_end],
+            " ".repeat(column),
+            "^".repeat(width),
+        )
+    }
+}
+
+fn char_offset(pattern: &str, offset: usize) -> usize {
+    pattern[..offset.min(pattern.len())].chars().count()
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ParsingErrorKind {
     #[error("Unexpected end of input")]
     UnexpectedEndOfInput,
     #[error("Missing {0}")]
     MissingCharacter(char),
+    #[error("Unexpected {0}")]
+    UnexpectedCharacter(char),
     #[error("Invalid escape sequence")]
     InvalidEscapeSequence,
     #[error("Invalid range quantifier")]
@@ -20,6 +85,54 @@ pub enum ParsingError {
     InvalidCharacterClass,
     #[error("Invalid capture name")]
     InvalidCaptureName,
+    #[error("Duplicate capture name {0}")]
+    DuplicateCaptureName(String),
     #[error("Range out of order")]
     RangeOutOfOrder,
+    #[error("Unknown flag {0}")]
+    UnknownFlag(char),
+    #[error("Unknown Unicode property {0}")]
+    UnknownUnicodeProperty(String),
+    #[error("Nesting limit of {0} exceeded")]
+    NestingLimitExceeded(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let error = ParsingError::new(
+            ParsingErrorKind::InvalidEscapeSequence,
+            Span::new(3, 5),
+            r"ab(\q)",
+        );
+        let expected = "\
+error: Invalid escape sequence
+ --> 1:4
+  |
+1 | ab(\\q)
+  |    ^^
+";
+
+        assert_eq!(error.render(r"ab(\q)"), expected);
+
+        let error = ParsingError::new(
+            ParsingErrorKind::UnexpectedEndOfInput,
+            Span::new(10, 10),
+            "ab\nここ\\",
+        );
+        let expected = "\
+error: Unexpected end of input
+ --> 2:4
+  |
+2 | ここ\\
+  |    ^
+";
+
+        assert_eq!(error.render("ab\nここ\\"), expected);
+        assert_eq!(error.char_span("ab\nここ\\"), Span::new(6, 6));
+        assert_eq!(error.to_string(), "Unexpected end of input at position 6");
+    }
 }
@@ -0,0 +1,151 @@
+use crate::unicode::in_ranges;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    ranges: Vec<(char, char)>,
+}
+
+impl IntervalSet {
+    pub fn new(ranges: impl IntoIterator<Item = (char, char)>) -> Self {
+        let mut sorted: Vec<_> = ranges
+            .into_iter()
+            .filter(|(lower, upper)| lower <= upper)
+            .collect();
+        let mut ranges: Vec<(char, char)> = Vec::with_capacity(sorted.len());
+
+        sorted.sort_unstable();
+
+        for (lower, upper) in sorted {
+            match ranges.last_mut() {
+                Some(last) if next_char(last.1).is_none_or(|next| lower <= next) => {
+                    last.1 = last.1.max(upper);
+                }
+                _ => ranges.push((lower, upper)),
+            }
+        }
+
+        Self { ranges }
+    }
+
+    pub fn ranges(&self) -> &[(char, char)] {
+        &self.ranges
+    }
+
+    pub fn contains(&self, ch: char) -> bool {
+        in_ranges(&self.ranges, ch)
+    }
+
+    pub fn union<'a>(sets: impl IntoIterator<Item = &'a Self>) -> Self {
+        Self::new(sets.into_iter().flat_map(|set| set.ranges.iter().copied()))
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+
+        while let (Some(&(a_lower, a_upper)), Some(&(b_lower, b_upper))) =
+            (self.ranges.get(i), other.ranges.get(j))
+        {
+            let lower = a_lower.max(b_lower);
+            let upper = a_upper.min(b_upper);
+
+            if lower <= upper {
+                ranges.push((lower, upper));
+            }
+
+            match a_upper < b_upper {
+                true => i += 1,
+                false => j += 1,
+            }
+        }
+
+        Self { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        self.intersection(&other.complement())
+    }
+
+    pub fn complement(&self) -> Self {
+        let mut ranges = Vec::new();
+        let mut start = Some('\0');
+
+        for &(lower, upper) in &self.ranges {
+            if let Some(start) = start.filter(|&start| start < lower) {
+                ranges.push((start, prev_char(lower)));
+            }
+
+            start = next_char(upper);
+        }
+
+        if let Some(start) = start {
+            ranges.push((start, char::MAX));
+        }
+
+        Self { ranges }
+    }
+}
+
+fn next_char(ch: char) -> Option<char> {
+    match ch {
+        '\u{D7FF}' => Some('\u{E000}'),
+        _ => char::from_u32(ch as u32 + 1),
+    }
+}
+
+fn prev_char(ch: char) -> char {
+    match ch {
+        '\u{E000}' => '\u{D7FF}',
+        _ => char::from_u32(ch as u32 - 1).unwrap_or(ch),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        let set = IntervalSet::new([('x', 'z'), ('a', 'c'), ('b', 'f'), ('g', 'g'), ('q', 'p')]);
+
+        assert_eq!(set.ranges(), &[('a', 'g'), ('x', 'z')]);
+        assert_eq!(
+            IntervalSet::new([('\u{E000}', '\u{E001}'), ('\0', '\u{D7FF}')]).ranges(),
+            &[('\0', '\u{E001}')]
+        );
+        assert!(set.contains('d'));
+        assert!(!set.contains('h'));
+        assert_eq!(IntervalSet::default().ranges(), &[]);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let letters = IntervalSet::new([('a', 'z'), ('A', 'Z')]);
+        let vowels = IntervalSet::new("aeiou".chars().map(|ch| (ch, ch)));
+        let digits = IntervalSet::new([('0', '9')]);
+
+        assert_eq!(
+            IntervalSet::union([&letters, &digits]).ranges(),
+            &[('0', '9'), ('A', 'Z'), ('a', 'z')]
+        );
+        assert_eq!(
+            letters
+                .intersection(&IntervalSet::new([('X', 'c')]))
+                .ranges(),
+            &[('X', 'Z'), ('a', 'c')]
+        );
+        assert_eq!(
+            letters.difference(&vowels).ranges()[1..4],
+            [('b', 'd'), ('f', 'h'), ('j', 'n')]
+        );
+        assert_eq!(
+            digits.complement().ranges(),
+            &[('\0', '/'), (':', char::MAX)]
+        );
+        assert_eq!(digits.complement().complement(), digits);
+        assert_eq!(
+            IntervalSet::default().complement().ranges(),
+            &[('\0', char::MAX)]
+        );
+    }
+}
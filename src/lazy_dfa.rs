@@ -0,0 +1,235 @@
+use crate::{
+    dfa::{Determinizer, DfaState, DfaStateId},
+    nfa::Nfa,
+    pikevm::MatchSemantics,
+};
+use std::collections::HashMap;
+
+pub const CACHE_CAPACITY: usize = 4096;
+const MAX_CACHE_CLEARS: usize = 8;
+
+#[derive(Debug)]
+pub struct CacheExhausted;
+
+#[derive(Debug, Clone, Copy)]
+struct DfaTransition {
+    next: DfaStateId,
+    is_match: bool,
+}
+
+#[derive(Debug)]
+pub struct DfaCache {
+    anchored: bool,
+    capacity: usize,
+    states: Vec<DfaState>,
+    ids: HashMap<DfaState, DfaStateId>,
+    transitions: HashMap<(DfaStateId, char), DfaTransition>,
+    eoi_matches: HashMap<DfaStateId, bool>,
+    clears: usize,
+}
+
+impl DfaCache {
+    pub fn new(anchored: bool, capacity: usize) -> Self {
+        Self {
+            anchored,
+            capacity,
+            states: Vec::new(),
+            ids: HashMap::new(),
+            transitions: HashMap::new(),
+            eoi_matches: HashMap::new(),
+            clears: 0,
+        }
+    }
+
+    fn insert(&mut self, state: DfaState) -> Result<DfaStateId, CacheExhausted> {
+        if let Some(&id) = self.ids.get(&state) {
+            return Ok(id);
+        }
+
+        if self.states.len() >= self.capacity {
+            self.clear()?;
+        }
+
+        let id = self.states.len();
+        self.ids.insert(state.clone(), id);
+        self.states.push(state);
+
+        Ok(id)
+    }
+
+    fn clear(&mut self) -> Result<(), CacheExhausted> {
+        self.clears += 1;
+
+        if self.clears > MAX_CACHE_CLEARS {
+            return Err(CacheExhausted);
+        }
+
+        self.states.clear();
+        self.ids.clear();
+        self.transitions.clear();
+        self.eoi_matches.clear();
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct LazyDfa<'a> {
+    determinizer: Determinizer<'a>,
+    cache: &'a mut DfaCache,
+}
+
+impl<'a> LazyDfa<'a> {
+    pub fn new(nfa: &'a Nfa, semantics: MatchSemantics, cache: &'a mut DfaCache) -> Self {
+        Self {
+            determinizer: Determinizer::new(nfa, semantics, cache.anchored),
+            cache,
+        }
+    }
+
+    pub fn is_match(&mut self, input: &str, start: usize) -> Result<bool, CacheExhausted> {
+        self.search(input, start, true).map(|end| end.is_some())
+    }
+
+    pub fn find_end(&mut self, input: &str, start: usize) -> Result<Option<usize>, CacheExhausted> {
+        self.search(input, start, false)
+    }
+
+    fn search(
+        &mut self,
+        input: &str,
+        start: usize,
+        earliest: bool,
+    ) -> Result<Option<usize>, CacheExhausted> {
+        let prev = input[..start].chars().next_back().into();
+        let mut end = None;
+
+        self.cache.clears = 0;
+
+        let mut id = self.cache.insert(self.determinizer.start(prev))?;
+
+        for (i, ch) in input[start..].char_indices() {
+            let transition = self.transition(id, ch)?;
+
+            if transition.is_match {
+                end = Some(start + i);
+
+                if earliest {
+                    return Ok(end);
+                }
+            }
+
+            if self
+                .determinizer
+                .is_dead(&self.cache.states[transition.next])
+            {
+                return Ok(end);
+            }
+
+            id = transition.next;
+        }
+
+        if self.eoi_match(id) {
+            end = Some(input.len());
+        }
+
+        Ok(end)
+    }
+
+    fn transition(&mut self, id: DfaStateId, ch: char) -> Result<DfaTransition, CacheExhausted> {
+        if let Some(&transition) = self.cache.transitions.get(&(id, ch)) {
+            return Ok(transition);
+        }
+
+        let (next, is_match) = self.determinizer.next(&self.cache.states[id], ch);
+        let clears = self.cache.clears;
+        let next = self.cache.insert(next)?;
+        let transition = DfaTransition { next, is_match };
+
+        if self.cache.clears == clears {
+            self.cache.transitions.insert((id, ch), transition);
+        }
+
+        Ok(transition)
+    }
+
+    fn eoi_match(&mut self, id: DfaStateId) -> bool {
+        if let Some(&is_match) = self.cache.eoi_matches.get(&id) {
+            return is_match;
+        }
+
+        let is_match = self.determinizer.eoi_match(&self.cache.states[id]);
+        self.cache.eoi_matches.insert(id, is_match);
+
+        is_match
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_regex;
+
+    fn find_end(regex: &str, input: &str, semantics: MatchSemantics) -> Option<usize> {
+        let nfa = Nfa::from(parse_regex(regex).unwrap());
+        let mut cache = DfaCache::new(true, CACHE_CAPACITY);
+
+        LazyDfa::new(&nfa, semantics, &mut cache)
+            .search(input, 0, false)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_find_end() {
+        assert_eq!(
+            find_end("a|ab", "ab", MatchSemantics::LeftmostFirst),
+            Some(1)
+        );
+        assert_eq!(
+            find_end("a|ab", "ab", MatchSemantics::LeftmostLongest),
+            Some(2)
+        );
+        assert_eq!(
+            find_end("<.+?>", "<a><b>", MatchSemantics::LeftmostFirst),
+            Some(3)
+        );
+        assert_eq!(
+            find_end(r"\bfoo\b", "foo bar", MatchSemantics::LeftmostFirst),
+            Some(3)
+        );
+        assert_eq!(
+            find_end(r"\bfoo\b", "foobar", MatchSemantics::LeftmostFirst),
+            None
+        );
+        assert_eq!(find_end("a*$", "aab", MatchSemantics::LeftmostFirst), None);
+
+        let nfa = Nfa::from(parse_regex("ab|b+").unwrap());
+        let mut cache = DfaCache::new(false, CACHE_CAPACITY);
+        let mut dfa = LazyDfa::new(&nfa, MatchSemantics::LeftmostFirst, &mut cache);
+
+        assert_eq!(dfa.find_end("xbbab", 0).unwrap(), Some(3));
+        assert_eq!(dfa.find_end("xabbb", 1).unwrap(), Some(3));
+        assert_eq!(dfa.find_end("xyz", 0).unwrap(), None);
+    }
+
+    #[test]
+    fn test_cache_exhaustion() {
+        let nfa = Nfa::from(parse_regex("(a|b)*a(a|b){12}c").unwrap());
+        let mut seed = 42u32;
+        let input: String = (0..10_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                if seed & 0x10000 == 0 {
+                    'a'
+                } else {
+                    'b'
+                }
+            })
+            .collect();
+        let mut cache = DfaCache::new(false, 64);
+        let mut dfa = LazyDfa::new(&nfa, MatchSemantics::LeftmostFirst, &mut cache);
+
+        assert!(dfa.is_match(&input, 0).is_err());
+        assert!(dfa.is_match(&format!("a{}c", "b".repeat(12)), 0).unwrap());
+    }
+}
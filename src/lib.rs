@@ -1,9 +1,19 @@
 mod ast;
+mod builder;
+mod dfa;
+mod interval_set;
+mod lazy_dfa;
 mod nfa;
 mod parser;
+mod pikevm;
+mod program;
 mod regex;
+mod unicode;
+mod unicode_tables;
 
 pub mod error;
+pub use builder::*;
+pub use pikevm::MatchSemantics;
 pub use regex::*;
 
 #[cfg(feature = "wasm")]
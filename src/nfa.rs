@@ -1,20 +1,26 @@
-use crate::ast::{CharacterClass, ClassMember, Group, Node, Range};
+use crate::{
+    ast::{Assertion, CharacterClass, Group, Node, Range},
+    error::Error,
+    parser::{is_ascii_word_char, is_word_char},
+};
 use std::{
-    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::{self, Debug},
 };
 
 pub const START: usize = 0;
+pub const DEFAULT_SIZE_LIMIT: usize = 1 << 20;
 
 pub type StateId = usize;
 pub type TransitionMap = BTreeMap<usize, Vec<Transition>>;
 
-#[derive(Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TransitionKind {
     Character(char),
     Epsilon,
     Wildcard,
     CharacterClass(CharacterClass),
+    Assertion(Assertion),
 }
 
 impl fmt::Display for TransitionKind {
@@ -27,11 +33,22 @@ impl fmt::Display for TransitionKind {
                 false => write!(f, "{ch}"),
             },
             TransitionKind::CharacterClass(class) => write!(f, "{class}"),
+            TransitionKind::Assertion(assertion) => write!(f, "{assertion}"),
         }
     }
 }
 
-#[derive(Clone, PartialEq)]
+impl TransitionKind {
+    pub fn ranges(&self) -> Vec<(char, char)> {
+        match self {
+            TransitionKind::Character(ch) => vec![(*ch, *ch)],
+            TransitionKind::CharacterClass(class) => class.set().ranges().to_vec(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Transition {
     pub(crate) kind: TransitionKind,
     pub(crate) end: StateId,
@@ -42,39 +59,119 @@ impl Transition {
         Self { kind, end }
     }
 
-    fn is_epsilon(&self) -> bool {
-        self.kind == TransitionKind::Epsilon
+    pub fn is_consuming(&self) -> bool {
+        !matches!(
+            self.kind,
+            TransitionKind::Epsilon | TransitionKind::Assertion(_)
+        )
+    }
+
+    pub fn is_epsilon(&self, context: &Context) -> bool {
+        match &self.kind {
+            TransitionKind::Epsilon => true,
+            TransitionKind::Assertion(assertion) => context.satisfies(assertion),
+            _ => false,
+        }
     }
 
-    fn accept(&self, input: &char) -> bool {
+    pub fn accept(&self, input: &char) -> bool {
         match &self.kind {
             TransitionKind::Character(ch) => ch == input,
             TransitionKind::Wildcard => true,
-            TransitionKind::Epsilon => false,
-            TransitionKind::CharacterClass(class) => {
-                let contains = class.members.iter().any(|c| match c {
-                    ClassMember::Atom(ch) => input == ch,
-                    ClassMember::Range(lower, upper) => lower <= input && upper >= input,
-                });
-
-                class.negate ^ contains
-            }
+            TransitionKind::Epsilon | TransitionKind::Assertion(_) => false,
+            TransitionKind::CharacterClass(class) => class.contains(input),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CharKind {
+    #[default]
+    End,
+    Newline,
+    AsciiWord,
+    Word,
+    Other,
+}
+
+impl From<Option<char>> for CharKind {
+    fn from(value: Option<char>) -> Self {
+        match value {
+            None => CharKind::End,
+            Some('\n') => CharKind::Newline,
+            Some(ch) if is_ascii_word_char(ch) => CharKind::AsciiWord,
+            Some(ch) if is_word_char(ch) => CharKind::Word,
+            Some(_) => CharKind::Other,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Context {
+    pub prev: CharKind,
+    pub next: CharKind,
+}
+
+impl Context {
+    pub fn new(input: &str, position: usize) -> Self {
+        Self {
+            prev: input[..position].chars().next_back().into(),
+            next: input[position..].chars().next().into(),
+        }
+    }
+
+    fn satisfies(&self, assertion: &Assertion) -> bool {
+        match assertion {
+            Assertion::StartOfInput => self.prev == CharKind::End,
+            Assertion::EndOfInput => self.next == CharKind::End,
+            Assertion::StartOfLine => matches!(self.prev, CharKind::End | CharKind::Newline),
+            Assertion::EndOfLine => matches!(self.next, CharKind::End | CharKind::Newline),
+            Assertion::WordBoundary => self.is_word_boundary(false),
+            Assertion::NotWordBoundary => !self.is_word_boundary(false),
+            Assertion::AsciiWordBoundary => self.is_word_boundary(true),
+            Assertion::AsciiNotWordBoundary => !self.is_word_boundary(true),
+        }
+    }
+
+    fn is_word_boundary(&self, ascii: bool) -> bool {
+        let is_word = |kind| match kind {
+            CharKind::AsciiWord => true,
+            CharKind::Word => !ascii,
+            _ => false,
+        };
+
+        is_word(self.prev) != is_word(self.next)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct CaptureGroup {
     pub start: StateId,
     pub end: StateId,
 }
 
+impl CaptureGroup {
+    fn offset(&self, offset: usize) -> Self {
+        Self {
+            start: self.start + offset,
+            end: self.end + offset,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaptureKind {
+    Indexed(usize),
+    Named(String),
+}
+
 #[derive(Clone, PartialEq)]
 pub struct Nfa {
     pub(crate) state_count: usize,
     pub(crate) transitions: TransitionMap,
     pub(crate) capture_groups: Vec<CaptureGroup>,
     pub(crate) named_capture_groups: HashMap<String, CaptureGroup>,
+    pub(crate) repeated_capture_groups: Vec<(CaptureKind, CaptureGroup)>,
 }
 
 impl Nfa {
@@ -82,6 +179,12 @@ impl Nfa {
         self.state_count - 1
     }
 
+    pub fn has_word_boundary(&self) -> bool {
+        self.transitions.values().flatten().any(|transition| {
+            matches!(&transition.kind, TransitionKind::Assertion(assertion) if assertion.is_word_boundary())
+        })
+    }
+
     fn epsilon() -> Self {
         NfaBuilder::default()
             .transition(START, TransitionKind::Epsilon, 1)
@@ -109,6 +212,15 @@ impl Nfa {
             .build()
     }
 
+    fn concatenate_repetition(self, other: Nfa) -> Self {
+        let offset = self.state_count;
+
+        NfaBuilder::from(self)
+            .extend_repetition(other, offset)
+            .transition(offset - 1, TransitionKind::Epsilon, offset)
+            .build()
+    }
+
     fn alternate(self, other: Nfa) -> Self {
         let offset = self.state_count + 1;
         let new_end = offset + other.state_count;
@@ -123,61 +235,118 @@ impl Nfa {
             .build()
     }
 
-    fn one_or_more(self) -> Self {
+    fn one_or_more(self, greedy: bool) -> Self {
         let offset = self.state_count;
+        let (first, second) = match greedy {
+            true => (1, offset + 1),
+            false => (offset + 1, 1),
+        };
 
         NfaBuilder::default()
             .transition(START, TransitionKind::Epsilon, 1)
             .extend(self, 1)
-            .transition(offset, TransitionKind::Epsilon, 1)
-            .transition(offset, TransitionKind::Epsilon, offset + 1)
+            .transition(offset, TransitionKind::Epsilon, first)
+            .transition(offset, TransitionKind::Epsilon, second)
             .build()
     }
 
-    fn zero_or_one(self) -> Self {
-        let end = self.end();
+    fn zero_or_one(self, greedy: bool) -> Self {
+        if greedy && !self.has_boundary_capture_group() {
+            let end = self.end();
 
-        NfaBuilder::from(self)
-            .transition(START, TransitionKind::Epsilon, end)
+            return NfaBuilder::from(self)
+                .transition(START, TransitionKind::Epsilon, end)
+                .build();
+        }
+
+        let new_end = self.state_count + 1;
+        let (first, second) = match greedy {
+            true => (1, new_end),
+            false => (new_end, 1),
+        };
+
+        NfaBuilder::default()
+            .transition(START, TransitionKind::Epsilon, first)
+            .transition(START, TransitionKind::Epsilon, second)
+            .extend(self, 1)
+            .transition(new_end - 1, TransitionKind::Epsilon, new_end)
+            .build()
+    }
+
+    fn unreachable(self) -> Self {
+        let new_end = self.state_count + 1;
+
+        NfaBuilder::default()
+            .transition(START, TransitionKind::Epsilon, new_end)
+            .extend(self, 1)
             .build()
     }
 
-    fn zero_or_more(self) -> Self {
-        Nfa::zero_or_one(self).one_or_more()
+    fn zero_or_more(self, greedy: bool) -> Self {
+        if self.is_nullable() {
+            return self.one_or_more(greedy).zero_or_one(greedy);
+        }
+
+        let new_end = self.state_count + 2;
+        let (first, second) = match greedy {
+            true => (2, new_end),
+            false => (new_end, 2),
+        };
+
+        NfaBuilder::default()
+            .transition(START, TransitionKind::Epsilon, 1)
+            .transition(1, TransitionKind::Epsilon, first)
+            .transition(1, TransitionKind::Epsilon, second)
+            .extend(self, 2)
+            .transition(new_end - 1, TransitionKind::Epsilon, 1)
+            .build()
     }
 
-    fn range(self, range: Range) -> Self {
-        let mut nfa = self;
-        let clone = nfa.clone();
+    fn range(self, range: Range, greedy: bool) -> Self {
+        match (range.min, range.max) {
+            (0, None) => return self.zero_or_more(greedy),
+            (1, None) => return self.one_or_more(greedy),
+            (0, Some(0)) => return self.unreachable(),
+            _ => {}
+        }
+
+        let clone = self.clone();
+        let mut nfa = match range.min {
+            0 => self.zero_or_one(greedy),
+            _ => self,
+        };
 
         for _ in 1..range.min {
-            nfa = nfa.concatenate(clone.clone())
+            nfa = nfa.concatenate_repetition(clone.clone())
         }
 
         if let Some(max) = range.max {
-            for _ in range.min..max {
-                nfa = nfa.concatenate(clone.clone().zero_or_one())
+            for _ in range.min.max(1)..max {
+                nfa = nfa.concatenate_repetition(clone.clone().zero_or_one(greedy))
             }
 
             nfa
         } else {
             let end = nfa.end();
+            let (first, second) = match greedy {
+                true => (end - clone.state_count, end + 1),
+                false => (end + 1, end - clone.state_count),
+            };
 
             NfaBuilder::from(nfa)
-                .transition(end, TransitionKind::Epsilon, end - clone.state_count)
-                .transition(end, TransitionKind::Epsilon, end + 1)
+                .transition(end, TransitionKind::Epsilon, first)
+                .transition(end, TransitionKind::Epsilon, second)
                 .build()
         }
     }
 
-    fn group(group: Group) -> Self {
-        let nfa = Nfa::from(*group.inner);
-        let end = nfa.end();
+    fn group(self, name: Option<String>, is_capturing: bool) -> Self {
+        let end = self.end();
 
-        match group.name {
-            Some(name) => NfaBuilder::from(nfa).named_group(START, end, name).build(),
-            None if group.is_capturing => NfaBuilder::from(nfa).group(START, end).build(),
-            None => nfa,
+        match name {
+            Some(name) => NfaBuilder::from(self).named_group(START, end, name).build(),
+            None if is_capturing => NfaBuilder::from(self).group(START, end).build(),
+            None => self,
         }
     }
 
@@ -187,37 +356,87 @@ impl Nfa {
             .build()
     }
 
-    pub fn epsilon_closure(&self, start: StateId) -> HashSet<StateId> {
-        let mut eclosure = HashSet::new();
-        let mut stack = VecDeque::new();
+    fn assertion(assertion: Assertion) -> Self {
+        NfaBuilder::default()
+            .transition(START, TransitionKind::Assertion(assertion), 1)
+            .build()
+    }
+
+    fn is_nullable(&self) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack = vec![START];
 
-        stack.push_back(start);
+        while let Some(state) = stack.pop() {
+            if visited.insert(state) {
+                let transitions = self.transitions.get(&state).into_iter().flatten();
 
-        while let Some(state) = stack.pop_back() {
-            if !eclosure.insert(state) {
-                continue;
+                stack.extend(transitions.filter(|t| !t.is_consuming()).map(|t| t.end));
             }
+        }
+
+        visited.contains(&self.end())
+    }
+
+    fn has_boundary_capture_group(&self) -> bool {
+        let end = self.end();
+
+        self.all_capture_groups()
+            .any(|(_, group)| group.start == START || group.end == end)
+    }
+
+    pub fn all_capture_groups(&self) -> impl Iterator<Item = (CaptureKind, &CaptureGroup)> {
+        let indexed = self
+            .capture_groups
+            .iter()
+            .enumerate()
+            .map(|(index, group)| (CaptureKind::Indexed(index), group));
+        let named = self
+            .named_capture_groups
+            .iter()
+            .map(|(name, group)| (CaptureKind::Named(name.to_owned()), group));
+        let repeated = self
+            .repeated_capture_groups
+            .iter()
+            .map(|(kind, group)| (kind.clone(), group));
 
-            if let Some(transitions) = self.transitions.get(&state) {
-                let eclosed_states = transitions
-                    .iter()
-                    .filter_map(|t| t.is_epsilon().then_some(t.end));
-                stack.extend(eclosed_states);
+        indexed.chain(named).chain(repeated)
+    }
+
+    pub fn epsilon_closure(&self, start: StateId, context: &Context) -> Vec<StateId> {
+        let mut visited = HashSet::new();
+        let mut eclosure = Vec::new();
+        let mut stack = vec![start];
+
+        while let Some(state) = stack.pop() {
+            if !visited.insert(state) {
+                continue;
             }
+
+            eclosure.push(state);
+            stack.extend(self.epsilon_transitions(state, context).rev());
         }
 
         eclosure
     }
 
-    pub fn next(&self, state: StateId, input: char) -> HashSet<StateId> {
+    pub fn epsilon_transitions<'a>(
+        &'a self,
+        state: StateId,
+        context: &'a Context,
+    ) -> impl DoubleEndedIterator<Item = StateId> + 'a {
         self.transitions
             .get(&state)
-            .map_or_else(HashSet::new, |transitions| {
-                transitions
-                    .iter()
-                    .filter_map(|t| t.accept(&input).then_some(t.end))
-                    .collect()
-            })
+            .into_iter()
+            .flatten()
+            .filter_map(|t| t.is_epsilon(context).then_some(t.end))
+    }
+
+    pub fn consuming_transitions(&self, state: StateId) -> impl Iterator<Item = &Transition> {
+        self.transitions
+            .get(&state)
+            .into_iter()
+            .flatten()
+            .filter(|t| t.is_consuming())
     }
 
     pub fn is_accepting(&self, state: StateId) -> bool {
@@ -225,24 +444,87 @@ impl Nfa {
     }
 }
 
-impl From<Node> for Nfa {
-    fn from(value: Node) -> Self {
-        match value {
+impl Nfa {
+    pub fn compile(value: Node, size_limit: usize) -> Result<Self, Error> {
+        let compile = |node: Box<Node>| Nfa::compile(*node, size_limit);
+        let nfa = match value {
             Node::Empty => Nfa::epsilon(),
             Node::Character(ch) => Nfa::character(ch),
             Node::Wildcard => Nfa::wildcard(),
-            Node::Group(group) => Nfa::group(group),
-            Node::Plus(node) => Nfa::from(*node).one_or_more(),
-            Node::Star(node) => Nfa::from(*node).zero_or_more(),
-            Node::Optional(node) => Nfa::from(*node).zero_or_one(),
-            Node::Concatenation(a, b) => Nfa::from(*a).concatenate(Nfa::from(*b)),
-            Node::Alternation(a, b) => Nfa::from(*a).alternate(Nfa::from(*b)),
-            Node::Range { inner, range } => Nfa::from(*inner).range(range),
+            Node::Group(Group {
+                inner,
+                name,
+                is_capturing,
+            }) => compile(inner)?.group(name, is_capturing),
+            Node::Plus(node) => compile(node)?.one_or_more(true),
+            Node::Star(node) => compile(node)?.zero_or_more(true),
+            Node::Optional(node) => compile(node)?.zero_or_one(true),
+            Node::LazyPlus(node) => compile(node)?.one_or_more(false),
+            Node::LazyStar(node) => compile(node)?.zero_or_more(false),
+            Node::LazyOptional(node) => compile(node)?.zero_or_one(false),
+            Node::Concatenation(lhs, rhs) => {
+                let mut nfa = compile(lhs)?;
+                let mut node = *rhs;
+
+                while let Node::Concatenation(lhs, rhs) = node {
+                    nfa = nfa.concatenate(compile(lhs)?).check_size(size_limit)?;
+                    node = *rhs;
+                }
+
+                nfa.concatenate(Nfa::compile(node, size_limit)?)
+            }
+            Node::Alternation(lhs, rhs) => {
+                let mut branches = vec![compile(lhs)?];
+                let mut node = *rhs;
+
+                while let Node::Alternation(lhs, rhs) = node {
+                    branches.push(compile(lhs)?);
+                    node = *rhs;
+                }
+
+                let last = Nfa::compile(node, size_limit)?;
+
+                branches
+                    .into_iter()
+                    .rev()
+                    .fold(last, |rhs, lhs| lhs.alternate(rhs))
+            }
+            Node::Range { inner, range } => {
+                compile(inner)?.bounded_range(range, true, size_limit)?
+            }
+            Node::LazyRange { inner, range } => {
+                compile(inner)?.bounded_range(range, false, size_limit)?
+            }
             Node::CharacterClass(class) => Nfa::class(class),
+            Node::Assertion(assertion) => Nfa::assertion(assertion),
+        };
+
+        nfa.check_size(size_limit)
+    }
+
+    fn check_size(self, size_limit: usize) -> Result<Self, Error> {
+        match self.state_count > size_limit {
+            true => Err(Error::NfaSizeLimitExceeded(size_limit)),
+            false => Ok(self),
+        }
+    }
+
+    fn bounded_range(self, range: Range, greedy: bool, size_limit: usize) -> Result<Self, Error> {
+        let copies = range.max.unwrap_or(range.min).max(1);
+
+        match self.state_count.saturating_mul(copies) > size_limit {
+            true => Err(Error::NfaSizeLimitExceeded(size_limit)),
+            false => Ok(self.range(range, greedy)),
         }
     }
 }
 
+impl From<Node> for Nfa {
+    fn from(value: Node) -> Self {
+        Nfa::compile(value, usize::MAX).expect("NFA without a size limit should compile")
+    }
+}
+
 impl Debug for Nfa {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "State count: {:?}", self.state_count)?;
@@ -265,6 +547,7 @@ pub struct NfaBuilder {
     transitions: TransitionMap,
     capture_groups: Vec<CaptureGroup>,
     named_capture_groups: HashMap<String, CaptureGroup>,
+    repeated_capture_groups: Vec<(CaptureKind, CaptureGroup)>,
 }
 
 impl NfaBuilder {
@@ -274,12 +557,7 @@ impl NfaBuilder {
 
         transitions.push(transition);
 
-        if from + 1 > self.state_count {
-            self.state_count += 1;
-        }
-        if to + 1 > self.state_count {
-            self.state_count += 1;
-        }
+        self.state_count = self.state_count.max(from + 1).max(to + 1);
     }
 
     fn transition(mut self, from: StateId, transition: TransitionKind, to: StateId) -> Self {
@@ -288,34 +566,59 @@ impl NfaBuilder {
     }
 
     fn extend(mut self, other: Nfa, offset: usize) -> Self {
-        for (start, transitions) in other.transitions {
-            for transition in transitions {
-                self.add_transition(start + offset, transition.kind, transition.end + offset);
-            }
-        }
+        let index_offset = self.capture_groups.len();
+
+        self.extend_transitions(other.transitions, offset);
 
         for group in other.capture_groups {
-            self.capture_groups.push(CaptureGroup {
-                start: group.start + offset,
-                end: group.end + offset,
-            });
+            self.capture_groups.push(group.offset(offset));
         }
 
         for (name, group) in other.named_capture_groups {
-            self.named_capture_groups.insert(
-                name,
-                CaptureGroup {
-                    start: group.start + offset,
-                    end: group.end + offset,
-                },
-            );
+            self.named_capture_groups.insert(name, group.offset(offset));
+        }
+
+        for (kind, group) in other.repeated_capture_groups {
+            let kind = match kind {
+                CaptureKind::Indexed(index) => CaptureKind::Indexed(index + index_offset),
+                kind => kind,
+            };
+
+            self.repeated_capture_groups
+                .push((kind, group.offset(offset)));
         }
 
         self
     }
 
+    fn extend_repetition(mut self, other: Nfa, offset: usize) -> Self {
+        let groups = other
+            .all_capture_groups()
+            .map(|(kind, group)| (kind, group.offset(offset)))
+            .collect::<Vec<_>>();
+
+        self.extend_transitions(other.transitions, offset);
+        self.repeated_capture_groups.extend(groups);
+        self
+    }
+
+    fn extend_transitions(&mut self, transitions: TransitionMap, offset: usize) {
+        for (start, transitions) in transitions {
+            for transition in transitions {
+                self.add_transition(start + offset, transition.kind, transition.end + offset);
+            }
+        }
+    }
+
     fn group(mut self, start: StateId, end: StateId) -> Self {
         self.capture_groups.insert(0, CaptureGroup { start, end });
+
+        for (kind, _) in &mut self.repeated_capture_groups {
+            if let CaptureKind::Indexed(index) = kind {
+                *index += 1;
+            }
+        }
+
         self
     }
 
@@ -331,6 +634,7 @@ impl NfaBuilder {
             transitions: self.transitions,
             capture_groups: self.capture_groups,
             named_capture_groups: self.named_capture_groups,
+            repeated_capture_groups: self.repeated_capture_groups,
         }
     }
 }
@@ -342,6 +646,7 @@ impl From<Nfa> for NfaBuilder {
             transitions: value.transitions,
             capture_groups: value.capture_groups,
             named_capture_groups: value.named_capture_groups,
+            repeated_capture_groups: value.repeated_capture_groups,
         }
     }
 }
@@ -424,6 +729,19 @@ mod tests {
         assert_eq!(expected, nfa);
     }
 
+    #[test]
+    fn test_lazy_optional() {
+        let expected = NfaBuilder::default()
+            .transition(0, TransitionKind::Epsilon, 3)
+            .transition(0, TransitionKind::Epsilon, 1)
+            .transition(1, TransitionKind::Character('e'), 2)
+            .transition(2, TransitionKind::Epsilon, 3)
+            .build();
+        let nfa = to_nfa("e??");
+
+        assert_eq!(expected, nfa);
+    }
+
     #[test]
     fn test_epsilon_closure() {
         let nfa = NfaBuilder::default()
@@ -431,16 +749,16 @@ mod tests {
             .transition(0, TransitionKind::Epsilon, 2)
             .transition(1, TransitionKind::Epsilon, 3)
             .build();
-        let expected = [0, 1, 2, 3].into_iter().collect();
-        let eclosure = nfa.epsilon_closure(0);
+        let expected = vec![0, 1, 3, 2];
+        let eclosure = nfa.epsilon_closure(0, &Context::default());
 
         assert_eq!(eclosure, expected);
 
         let nfa = NfaBuilder::default()
             .transition(0, TransitionKind::Character('a'), 1)
             .build();
-        let eclosure = nfa.epsilon_closure(0);
-        let expected = [0].into_iter().collect();
+        let eclosure = nfa.epsilon_closure(0, &Context::default());
+        let expected = vec![0];
 
         assert_eq!(eclosure, expected);
 
@@ -449,8 +767,8 @@ mod tests {
             .transition(1, TransitionKind::Epsilon, 2)
             .transition(2, TransitionKind::Epsilon, 1)
             .build();
-        let expected = [0, 1, 2].into_iter().collect();
-        let eclosure = nfa.epsilon_closure(0);
+        let expected = vec![0, 1, 2];
+        let eclosure = nfa.epsilon_closure(0, &Context::default());
 
         assert_eq!(eclosure, expected);
     }
@@ -467,4 +785,55 @@ mod tests {
 
         assert_eq!(nfa.capture_groups, expected);
     }
+
+    #[test]
+    fn test_repeated_capture_groups() {
+        let nfa = to_nfa("(a){2}(b)");
+        let expected = vec![
+            CaptureGroup { start: 0, end: 1 },
+            CaptureGroup { start: 4, end: 5 },
+        ];
+
+        assert_eq!(nfa.capture_groups, expected);
+        assert_eq!(
+            nfa.repeated_capture_groups,
+            vec![(CaptureKind::Indexed(0), CaptureGroup { start: 2, end: 3 })]
+        );
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let ast = parse_regex("(a|b){3}").unwrap();
+        let nfa = Nfa::compile(ast, 64).unwrap();
+
+        assert_eq!(nfa, to_nfa("(a|b){3}"));
+
+        let ast = parse_regex("(a|b){3}").unwrap();
+
+        assert!(Nfa::compile(ast, 8).is_err());
+
+        let ast = parse_regex("((a{1000}){1000}){1000}").unwrap();
+
+        assert!(Nfa::compile(ast, DEFAULT_SIZE_LIMIT).is_err());
+    }
+
+    #[test]
+    fn test_class_ranges() {
+        let Node::CharacterClass(class) = parse_regex("[c-fa-de[x]]").unwrap() else {
+            unreachable!()
+        };
+        let transition = Transition::new(TransitionKind::CharacterClass(class), 1);
+
+        assert_eq!(transition.kind.ranges(), vec![('a', 'f'), ('x', 'x')]);
+        assert!(transition.accept(&'b'));
+        assert!(!transition.accept(&'g'));
+
+        let Node::CharacterClass(class) = parse_regex("[^\\0-\\x{10FFFE}]").unwrap() else {
+            unreachable!()
+        };
+        let transition = Transition::new(TransitionKind::CharacterClass(class), 1);
+
+        assert_eq!(transition.kind.ranges(), vec![(char::MAX, char::MAX)]);
+        assert!(transition.accept(&char::MAX));
+    }
 }
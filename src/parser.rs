@@ -1,163 +1,785 @@
 use crate::{
-    ast::{ClassMember, Node, Range},
-    error::ParsingError,
+    ast::{Assertion, CharacterClass, ClassMember, Node, Range, UnicodeProperty},
+    error::{ParsingError, ParsingErrorKind, Span},
+    unicode::{case_variants, in_ranges, lookup_property},
+    unicode_tables::{PERL_DIGIT, PERL_SPACE, PERL_WORD},
 };
+use std::collections::HashSet;
 
 type Result<T> = std::result::Result<T, ParsingError>;
 
-pub fn parse_regex(input: &str) -> Result<Node> {
-    parse_alternation(input).map(|(result, _)| result)
+pub const DEFAULT_NEST_LIMIT: usize = 250;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Flags {
+    pub multiline: bool,
+    pub case_insensitive: bool,
+    pub dot_matches_newline: bool,
+    pub ignore_whitespace: bool,
+    pub unicode: bool,
 }
 
-fn parse_alternation(input: &str) -> Result<(Node, &str)> {
-    let (lhs, rest) = parse_concat(input)?;
-    match rest.chars().next() {
-        Some('|') => {
-            parse_alternation(&rest[1..]).map(|(rhs, rest)| (Node::alternation(lhs, rhs), rest))
+impl Default for Flags {
+    fn default() -> Self {
+        Self {
+            multiline: false,
+            case_insensitive: false,
+            dot_matches_newline: false,
+            ignore_whitespace: false,
+            unicode: true,
         }
-        _ => Ok((lhs, rest)),
     }
 }
 
-fn parse_concat(input: &str) -> Result<(Node, &str)> {
-    let (lhs, rest) = parse_quantifier(input)?;
-    match rest.chars().next() {
-        Some('|') | Some(')') | None => Ok((lhs, rest)),
-        Some(_) => parse_concat(rest).map(|(rhs, rest)| (Node::concatenation(lhs, rhs), rest)),
-    }
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParserConfig {
+    pub flags: Flags,
+    pub nest_limit: usize,
+    pub legacy_non_capturing: bool,
 }
 
-fn parse_quantifier(input: &str) -> Result<(Node, &str)> {
-    let (result, rest) = parser_atom(input)?;
-    match rest.chars().next() {
-        Some('+') => Ok((Node::plus(result), &rest[1..])),
-        Some('*') => Ok((Node::star(result), &rest[1..])),
-        Some('?') => Ok((Node::optional(result), &rest[1..])),
-        Some('{') => {
-            parse_range(&rest[1..]).map(|(range, rest)| (Node::range(result, range), rest))
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self {
+            flags: Flags::default(),
+            nest_limit: DEFAULT_NEST_LIMIT,
+            legacy_non_capturing: false,
         }
-        _ => Ok((result, rest)),
     }
 }
 
-fn parse_range(input: &str) -> Result<(Range, &str)> {
-    let (lower, rest) = take_number(input)?;
-    match (lower, rest.chars().next()) {
-        (Some(lower), Some(',')) => {
-            parse_range_upper(&rest[1..]).map(|(upper, rest)| (Range::new(lower, upper), rest))
+#[cfg(test)]
+pub fn parse_regex(input: &str) -> Result<Node> {
+    parse_regex_with_flags(input, Flags::default())
+}
+
+#[cfg(test)]
+pub fn parse_regex_with_flags(input: &str, flags: Flags) -> Result<Node> {
+    let config = ParserConfig {
+        flags,
+        ..Default::default()
+    };
+
+    parse_regex_with_config(input, config)
+}
+
+pub fn parse_regex_with_config(input: &str, config: ParserConfig) -> Result<Node> {
+    Parser::new(input, config, false).parse()
+}
+
+pub fn parse_regex_recovering(input: &str, config: ParserConfig) -> (Node, Vec<ParsingError>) {
+    let mut parser = Parser::new(input, config, true);
+    let result = parser.parse().unwrap_or_else(|error| {
+        parser.errors.push(error);
+        Node::Empty
+    });
+
+    (result, parser.errors)
+}
+
+struct Parser<'p> {
+    pattern: &'p str,
+    flags: Flags,
+    nest_limit: usize,
+    legacy_non_capturing: bool,
+    capture_names: HashSet<&'p str>,
+    depth: usize,
+    recover: bool,
+    errors: Vec<ParsingError>,
+}
+
+impl<'p> Parser<'p> {
+    fn new(pattern: &'p str, config: ParserConfig, recover: bool) -> Self {
+        Self {
+            pattern,
+            flags: config.flags,
+            nest_limit: config.nest_limit,
+            legacy_non_capturing: config.legacy_non_capturing,
+            capture_names: HashSet::new(),
+            depth: 0,
+            recover,
+            errors: Vec::new(),
         }
-        (Some(lower), Some('}')) => Ok((Range::new(lower, Some(lower)), &rest[1..])),
-        _ => Err(ParsingError::InvalidRangeQuantifier),
     }
-}
 
-fn parse_range_upper(input: &str) -> Result<(Option<usize>, &str)> {
-    match input.chars().next() {
-        Some('}') => Ok((None, &input[1..])),
-        Some(_) => {
-            let (number, rest) = take_number(input)?;
-            match (number, rest.chars().next()) {
-                (Some(number), Some('}')) => Ok((Some(number), &rest[1..])),
-                _ => Err(ParsingError::InvalidRangeQuantifier),
+    fn parse(&mut self) -> Result<Node> {
+        let (mut result, mut rest) = self.parse_alternation(self.pattern)?;
+
+        while let Some(next) = rest.strip_prefix(')') {
+            let start = self.offset(rest);
+            let kind = ParsingErrorKind::UnexpectedCharacter(')');
+            let error = self.error(kind, start, start + 1);
+
+            self.report(error, ())?;
+
+            let (rhs, next) = self.parse_alternation(next)?;
+
+            result = Node::concatenation(result, rhs);
+            rest = next;
+        }
+
+        Ok(result)
+    }
+
+    fn parse_alternation(&mut self, input: &'p str) -> Result<(Node, &'p str)> {
+        let (branch, mut rest) = self.parse_concat(input)?;
+        let mut branches = vec![branch];
+
+        while let Some(next) = rest.strip_prefix('|') {
+            let (branch, next) = self.parse_concat(next)?;
+            branches.push(branch);
+            rest = next;
+        }
+
+        Ok((fold_right(branches, Node::alternation), rest))
+    }
+
+    fn parse_concat(&mut self, input: &'p str) -> Result<(Node, &'p str)> {
+        let mut nodes = Vec::new();
+        let mut rest = input;
+
+        loop {
+            let (node, next) = self.parse_quantifier(self.skip_ignored(rest))?;
+            nodes.push(node);
+            rest = next;
+
+            if matches!(rest.chars().next(), Some('|') | Some(')') | None) {
+                break;
             }
         }
-        None => Err(ParsingError::InvalidRangeQuantifier),
+
+        Ok((fold_right(nodes, Node::concatenation), rest))
     }
-}
 
-fn parser_atom(input: &str) -> Result<(Node, &str)> {
-    match input.chars().next() {
-        Some(c) => match c {
-            '(' => parse_group(&input[1..]),
-            '[' => parse_class(&input[1..]),
-            '\\' => parse_metachar(&input[1..]),
-            '.' => Ok((Node::Wildcard, &input[1..])),
-            ')' => Ok((Node::Empty, input)),
-            _ => {
-                let rest = &input[c.len_utf8()..];
-                Ok((Node::Character(c), rest))
+    fn parse_quantifier(&mut self, input: &'p str) -> Result<(Node, &'p str)> {
+        let (result, rest) = self.parser_atom(input)?;
+        let result = self.fold_case(result);
+        let rest = self.skip_ignored(rest);
+        let (result, rest) = match rest.chars().next() {
+            Some('+') => (Node::plus(result), &rest[1..]),
+            Some('*') => (Node::star(result), &rest[1..]),
+            Some('?') => (Node::optional(result), &rest[1..]),
+            Some('{') => match self.parse_range(&rest[1..]) {
+                Ok((range, rest)) => (Node::range(result, range), rest),
+                Err(error) => {
+                    let rest = &self.pattern[error.span.end..];
+                    self.report(error, (result, rest))?
+                }
+            },
+            _ => return Ok((result, rest)),
+        };
+
+        let rest = self.skip_ignored(rest);
+
+        match rest.strip_prefix('?') {
+            Some(rest) => Ok((result.lazy(), self.skip_ignored(rest))),
+            None => Ok((result, rest)),
+        }
+    }
+
+    fn parser_atom(&mut self, input: &'p str) -> Result<(Node, &'p str)> {
+        match input.chars().next() {
+            Some(c) => match c {
+                '(' => self.parse_group(&input[1..]),
+                '[' => self.parse_class(&input[1..]),
+                '\\' => self.parse_metachar(&input[1..]),
+                '.' => Ok((self.wildcard(), &input[1..])),
+                '^' => Ok((Node::Assertion(self.start_assertion()), &input[1..])),
+                '$' => Ok((Node::Assertion(self.end_assertion()), &input[1..])),
+                ')' | '|' => Ok((Node::Empty, input)),
+                _ => {
+                    let rest = &input[c.len_utf8()..];
+                    Ok((Node::Character(c), rest))
+                }
+            },
+            None => Ok((Node::Empty, input)),
+        }
+    }
+
+    fn parse_group(&mut self, input: &'p str) -> Result<(Node, &'p str)> {
+        self.depth += 1;
+
+        if self.depth > self.nest_limit {
+            let start = self.offset(input) - 1;
+            let kind = ParsingErrorKind::NestingLimitExceeded(self.nest_limit);
+            return Err(self.error(kind, start, start + 1));
+        }
+
+        let result = self.parse_group_inner(input);
+        self.depth -= 1;
+
+        result
+    }
+
+    fn parse_group_inner(&mut self, input: &'p str) -> Result<(Node, &'p str)> {
+        let flags = self.flags;
+        let (is_capturing, name, rest) = match input.get(..2) {
+            Some("?:") => (false, None, &input[2..]),
+            Some(":?") if self.legacy_non_capturing => (false, None, &input[2..]),
+            Some("?<") if input[2..].starts_with(['=', '!']) => self.unsupported_group(input)?,
+            Some("?<") => self.parse_capture_name(input, 2, '>')?,
+            Some("?'") => self.parse_capture_name(input, 2, '\'')?,
+            _ if input.starts_with("?P<") => self.parse_capture_name(input, 3, '>')?,
+            _ if is_flag_group(input) => {
+                let (flags, rest) = self.parse_flags(&input[1..], self.flags)?;
+                self.flags = flags;
+
+                match rest.strip_prefix(':') {
+                    Some(rest) => (false, None, rest),
+                    None => return self.close_flag_group(rest),
+                }
             }
-        },
-        None => Ok((Node::Empty, input)),
+            _ if input.starts_with('?') => self.unsupported_group(input)?,
+            _ => (true, None, input),
+        };
+
+        let (result, rest) = self.parse_alternation(rest)?;
+        self.flags = flags;
+
+        let group = Node::group(result, is_capturing, name);
+
+        match rest.strip_prefix(')') {
+            Some(rest) => Ok((group, rest)),
+            None => {
+                let start = self.offset(input) - 1;
+                let kind = ParsingErrorKind::MissingCharacter(')');
+                let error = self.error(kind, start, start + 1);
+                self.report(error, (group, rest))
+            }
+        }
     }
-}
 
-fn parse_metachar(input: &str) -> Result<(Node, &str)> {
-    match input.chars().next() {
-        Some(ch) if needs_escape(ch) => Ok((Node::Character(ch), &input[1..])),
-        Some(ch) => get_range_alias(ch)
-            .map(|range| (range, &input[1..]))
-            .ok_or(ParsingError::InvalidEscapeSequence),
-        None => Err(ParsingError::UnexpectedEndOfInput),
+    fn parse_capture_name(
+        &mut self,
+        input: &'p str,
+        prefix: usize,
+        close: char,
+    ) -> Result<(bool, Option<&'p str>, &'p str)> {
+        let (name, rest) = take_identifier(&input[prefix..]);
+
+        if name.is_empty() || !rest.starts_with(close) {
+            let start = self.offset(input) + prefix - 1;
+            let end = self.offset(rest) + rest.find(close).map_or(0, |i| i + 1);
+            let kind = ParsingErrorKind::InvalidCaptureName;
+            let error = self.error(kind, start, end);
+            self.report(error, (true, None, &self.pattern[end..]))
+        } else if !self.capture_names.insert(name) {
+            let start = self.offset(input) + prefix;
+            let kind = ParsingErrorKind::DuplicateCaptureName(name.to_string());
+            let error = self.error(kind, start, start + name.len());
+            self.report(error, (true, None, &rest[1..]))
+        } else {
+            Ok((true, Some(name), &rest[1..]))
+        }
     }
-}
 
-fn parse_class(input: &str) -> Result<(Node, &str)> {
-    let (negate, rest) = match input.get(..1) {
-        Some("^") => (true, &input[1..]),
-        _ => (false, input),
-    };
+    fn unsupported_group(&mut self, input: &'p str) -> Result<(bool, Option<&'p str>, &'p str)> {
+        let prefix = match input.starts_with("?<") {
+            true => 2,
+            false => 1,
+        };
 
-    parse_class_members(rest).map(|(members, rest)| (Node::class(negate, members), rest))
-}
+        match take_char(&input[prefix..]) {
+            (Some(ch), rest) => {
+                let start = self.offset(input);
+                let kind = ParsingErrorKind::UnexpectedCharacter(ch);
+                let error = self.error(kind, start, self.offset(rest));
+                self.report(error, (false, None, rest))
+            }
+            (None, rest) => {
+                let error = self.end_of_input();
+                self.report(error, (false, None, rest))
+            }
+        }
+    }
 
-fn parse_class_members(input: &str) -> Result<(Vec<ClassMember>, &str)> {
-    parse_class_members_inner(input, Vec::new())
-}
+    fn parse_range(&self, input: &'p str) -> Result<(Range, &'p str)> {
+        let (lower, rest) = take_number(self.skip_ignored(input))?;
+        let rest = self.skip_ignored(rest);
+        match (lower, rest.chars().next()) {
+            (Some(lower), Some(',')) => self
+                .parse_range_upper(input, self.skip_ignored(&rest[1..]))
+                .map(|(upper, rest)| (Range::new(lower, upper), rest)),
+            (Some(lower), Some('}')) => Ok((Range::new(lower, Some(lower)), &rest[1..])),
+            _ => Err(self.range_error(input, rest)),
+        }
+    }
+
+    fn parse_range_upper(
+        &self,
+        start: &'p str,
+        input: &'p str,
+    ) -> Result<(Option<usize>, &'p str)> {
+        match input.chars().next() {
+            Some('}') => Ok((None, &input[1..])),
+            Some(_) => {
+                let (number, rest) = take_number(input)?;
+                let rest = self.skip_ignored(rest);
+                match (number, rest.chars().next()) {
+                    (Some(number), Some('}')) => Ok((Some(number), &rest[1..])),
+                    _ => Err(self.range_error(start, rest)),
+                }
+            }
+            None => Err(self.range_error(start, input)),
+        }
+    }
+
+    fn parse_metachar(&mut self, input: &'p str) -> Result<(Node, &'p str)> {
+        if let Some((ch, rest)) = self.parse_escape(input)? {
+            return Ok((Node::Character(ch), rest));
+        }
 
-fn parse_class_members_inner(
-    input: &str,
-    acc: Vec<ClassMember>,
-) -> Result<(Vec<ClassMember>, &str)> {
-    let (ch, is_escaped, rest) = parse_char(input)?;
+        match input.chars().next() {
+            Some('b') => Ok((Node::Assertion(self.word_boundary(false)), &input[1..])),
+            Some('B') => Ok((Node::Assertion(self.word_boundary(true)), &input[1..])),
+            Some(ch @ ('p' | 'P')) => self
+                .parse_property(&input[1..], ch == 'P')
+                .map(|(members, rest)| (Node::CharacterClass(self.class(false, members)), rest)),
+            Some(ch) => {
+                let rest = &input[ch.len_utf8()..];
+                let start = self.offset(input) - 1;
 
-    if ch == ']' && !is_escaped {
-        return Ok((acc, rest));
+                match get_range_alias(ch, self.flags) {
+                    Some(class) => Ok((Node::CharacterClass(class), rest)),
+                    None => {
+                        let error = self.escape_error(start, rest);
+                        self.report(error, (Node::Character(ch), rest))
+                    }
+                }
+            }
+            None => {
+                let error = self.end_of_input();
+                self.report(error, (Node::Empty, input))
+            }
+        }
     }
 
-    if let Some(rest) = rest.strip_prefix('-') {
-        let (upper, _, rest) = parse_char(rest)?;
-        let acc = [acc, vec![ClassMember::Range(ch, upper)]].concat();
-        parse_class_members_inner(rest, acc)
-    } else {
-        let acc = [acc, vec![ClassMember::Atom(ch)]].concat();
-        parse_class_members_inner(rest, acc)
+    fn parse_class(&mut self, input: &'p str) -> Result<(Node, &'p str)> {
+        self.parse_nested_class(input)
+            .map(|(class, rest)| (Node::CharacterClass(class), rest))
     }
-}
 
-fn parse_char(input: &str) -> Result<(char, bool, &str)> {
-    match take_char(input) {
-        (Some('\\'), rest) => match take_char(rest) {
-            (Some(next), rest) => needs_escape(next)
-                .then_some((next, true, rest))
-                .ok_or(ParsingError::InvalidEscapeSequence),
-            _ => Err(ParsingError::UnexpectedEndOfInput),
-        },
-        (Some(ch), rest) => Ok((ch, false, rest)),
-        _ => Err(ParsingError::UnexpectedEndOfInput),
+    fn parse_nested_class(&mut self, input: &'p str) -> Result<(CharacterClass, &'p str)> {
+        self.depth += 1;
+
+        if self.depth > self.nest_limit {
+            let start = self.offset(input) - 1;
+            let kind = ParsingErrorKind::NestingLimitExceeded(self.nest_limit);
+            return Err(self.error(kind, start, start + 1));
+        }
+
+        let (negate, rest) = match input.get(..1) {
+            Some("^") => (true, &input[1..]),
+            _ => (false, input),
+        };
+        let result = self
+            .parse_class_members(rest)
+            .map(|(members, rest)| (self.class(negate, members), rest));
+        self.depth -= 1;
+
+        result
     }
-}
 
-fn parse_group(input: &str) -> Result<(Node, &str)> {
-    let (is_capturing, name, rest) = match input.get(..2) {
-        Some(":?") => (false, None, &input[2..]),
-        Some("?<") => {
-            let (name, rest) = take_alphabetic(&input[2..]);
+    fn parse_class_members(&mut self, input: &'p str) -> Result<(Vec<ClassMember>, &'p str)> {
+        let (mut members, mut rest) = match input.strip_prefix(']') {
+            Some(rest) => return Ok((Vec::new(), rest)),
+            None => self.parse_class_members_inner(input)?,
+        };
+
+        while let Some((operator, operand)) = take_class_operator(rest) {
+            let (rhs, next) = self.parse_class_members_inner(operand)?;
+            let lhs = self.class(false, members);
+            let rhs = self.class(false, rhs);
+
+            members = vec![match operator {
+                "&&" => ClassMember::Intersection(lhs, rhs),
+                _ => ClassMember::Difference(lhs, rhs),
+            }];
+            rest = next;
+        }
 
-            if name.is_empty() || !rest.starts_with('>') {
-                return Err(ParsingError::InvalidCaptureName);
+        match rest.strip_prefix(']') {
+            Some(rest) => Ok((members, rest)),
+            None => {
+                let error = self.end_of_input();
+                self.report(error, (members, rest))
             }
+        }
+    }
 
-            (true, Some(name), &rest[1..])
+    fn parse_class_members_inner(&mut self, input: &'p str) -> Result<(Vec<ClassMember>, &'p str)> {
+        let mut members = Vec::new();
+        let mut rest = input;
+
+        while !rest.is_empty() && !rest.starts_with(']') && take_class_operator(rest).is_none() {
+            rest = self.parse_class_member(rest, &mut members)?;
         }
-        _ => (true, None, input),
-    };
 
-    let (result, rest) = parse_alternation(rest)?;
-    match rest.get(..1) {
-        Some(")") => Ok((Node::group(result, is_capturing, name), &rest[1..])),
-        _ => Err(ParsingError::MissingCharacter(')')),
+        Ok((members, rest))
+    }
+
+    fn parse_class_member(
+        &mut self,
+        input: &'p str,
+        members: &mut Vec<ClassMember>,
+    ) -> Result<&'p str> {
+        if let Some((posix, rest)) = self.parse_posix_class(input)? {
+            members.extend(posix);
+            return Ok(rest);
+        }
+
+        let alias = input
+            .strip_prefix('\\')
+            .and_then(|rest| take_char(rest).0)
+            .and_then(|ch| get_range_alias(ch, self.flags));
+
+        if let Some(class) = alias {
+            members.push(ClassMember::Class(class));
+            return Ok(&input[2..]);
+        }
+
+        if let Some(property) = input.strip_prefix("\\p").or(input.strip_prefix("\\P")) {
+            let (property, rest) = self.parse_property(property, input.starts_with("\\P"))?;
+            members.extend(property);
+            return Ok(rest);
+        }
+
+        if let Some(nested) = input.strip_prefix('[') {
+            let (class, rest) = self.parse_nested_class(nested)?;
+            members.push(ClassMember::Class(class));
+            return Ok(rest);
+        }
+
+        let (ch, _, rest) = self.parse_char(input)?;
+        let range_end = rest
+            .strip_prefix('-')
+            .filter(|next| !next.is_empty() && !next.starts_with(']'))
+            .filter(|_| take_class_operator(rest).is_none());
+
+        let Some(rest) = range_end else {
+            members.push(ClassMember::Atom(ch));
+            return Ok(rest);
+        };
+        let (upper, _, rest) = self.parse_char(rest)?;
+
+        if upper < ch {
+            let kind = ParsingErrorKind::RangeOutOfOrder;
+            let error = self.error(kind, self.offset(input), self.offset(rest));
+            self.report(error, ())?;
+        } else {
+            members.push(ClassMember::Range(ch, upper));
+        }
+
+        Ok(rest)
+    }
+
+    fn parse_posix_class(&mut self, input: &'p str) -> Result<Option<(Vec<ClassMember>, &'p str)>> {
+        let Some((body, _)) = input
+            .strip_prefix("[:")
+            .and_then(|rest| rest.split_once(":]"))
+        else {
+            return Ok(None);
+        };
+        let (negate, name) = match body.strip_prefix('^') {
+            Some(name) => (true, name),
+            None => (false, body),
+        };
+
+        if name.is_empty() || !name.chars().all(|ch| ch.is_ascii_alphabetic()) {
+            return Ok(None);
+        }
+
+        let rest = &input[body.len() + 4..];
+
+        match POSIX_CLASSES.iter().find(|&&(key, _)| key == name) {
+            Some(&(name, ranges)) => {
+                let property = UnicodeProperty {
+                    name,
+                    negate,
+                    ranges,
+                };
+                Ok(Some((vec![ClassMember::Property(property)], rest)))
+            }
+            None => {
+                let kind = ParsingErrorKind::InvalidCharacterClass;
+                let error = self.error(kind, self.offset(input), self.offset(rest));
+                self.report(error, Some((Vec::new(), rest)))
+            }
+        }
+    }
+
+    fn parse_char(&mut self, input: &'p str) -> Result<(char, bool, &'p str)> {
+        match take_char(input) {
+            (Some('\\'), rest) => match (self.parse_escape(rest)?, take_char(rest)) {
+                (Some((ch, rest)), _) => Ok((ch, true, rest)),
+                (None, (Some(next), rest)) => {
+                    let error = self.escape_error(self.offset(input), rest);
+                    self.report(error, (next, true, rest))
+                }
+                _ => {
+                    let error = self.end_of_input();
+                    self.report(error, ('\\', true, rest))
+                }
+            },
+            (Some(ch), rest) => Ok((ch, false, rest)),
+            _ => Err(self.end_of_input()),
+        }
+    }
+
+    fn parse_escape(&mut self, input: &'p str) -> Result<Option<(char, &'p str)>> {
+        let (Some(ch), rest) = take_char(input) else {
+            return Ok(None);
+        };
+
+        let ch = match ch {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'f' => '\x0C',
+            'v' => '\x0B',
+            'a' => '\x07',
+            '0' => '\0',
+            'x' => return self.parse_code_point(input, rest, 2).map(Some),
+            'u' => return self.parse_code_point(input, rest, 4).map(Some),
+            ch if is_escapable(ch) => ch,
+            _ => return Ok(None),
+        };
+
+        Ok(Some((ch, rest)))
+    }
+
+    fn parse_code_point(
+        &mut self,
+        input: &'p str,
+        digits: &'p str,
+        width: usize,
+    ) -> Result<(char, &'p str)> {
+        let (hex, rest) = match digits.strip_prefix('{') {
+            Some(braced) => match braced.split_once('}') {
+                Some((hex, _)) => (hex, &braced[hex.len() + 1..]),
+                None => (braced, ""),
+            },
+            None => {
+                let (hex, _) = take_while(|ch| ch.is_ascii_hexdigit())(digits);
+                let end = hex.len().min(width);
+                (&digits[..end], &digits[end..])
+            }
+        };
+        let is_complete = digits.starts_with('{') || hex.len() == width;
+        let code_point = (is_complete && hex.len() <= 6)
+            .then(|| u32::from_str_radix(hex, 16).ok())
+            .flatten()
+            .and_then(char::from_u32);
+
+        match code_point {
+            Some(ch) => Ok((ch, rest)),
+            None => {
+                let error = self.escape_error(self.offset(input) - 1, rest);
+                self.report(error, (input.chars().next().unwrap_or('\\'), rest))
+            }
+        }
+    }
+
+    fn parse_property(
+        &mut self,
+        input: &'p str,
+        negate: bool,
+    ) -> Result<(Vec<ClassMember>, &'p str)> {
+        let start = self.offset(input) - 2;
+        let input = self.skip_ignored(input);
+        let (name, rest) = match input.strip_prefix('{') {
+            Some(braced) => match braced.split_once('}') {
+                Some((name, _)) => (name, &braced[name.len() + 1..]),
+                None => {
+                    let kind = ParsingErrorKind::MissingCharacter('}');
+                    let error = self.error(kind, start, self.pattern.len());
+                    return self.report(error, (Vec::new(), ""));
+                }
+            },
+            None => match take_char(input) {
+                (Some(ch), rest) => (&input[..ch.len_utf8()], rest),
+                (None, rest) => {
+                    let error = self.end_of_input();
+                    return self.report(error, (Vec::new(), rest));
+                }
+            },
+        };
+
+        if name.trim().is_empty() {
+            let kind = ParsingErrorKind::InvalidEscapeSequence;
+            let error = self.error(kind, start, start + 2);
+            return self.report(error, (Vec::new(), rest));
+        }
+
+        match lookup_property(name) {
+            Some((name, ranges)) => {
+                let property = UnicodeProperty {
+                    name,
+                    negate,
+                    ranges,
+                };
+                Ok((vec![ClassMember::Property(property)], rest))
+            }
+            None => {
+                let kind = ParsingErrorKind::UnknownUnicodeProperty(name.to_string());
+                let error = self.error(kind, start, self.offset(rest));
+                self.report(error, (Vec::new(), rest))
+            }
+        }
+    }
+
+    fn parse_flags(&mut self, input: &'p str, flags: Flags) -> Result<(Flags, &'p str)> {
+        let (names, rest) = take_while(|ch| ch.is_ascii_alphabetic() || ch == '-')(input);
+        let mut flags = flags;
+        let mut enable = true;
+
+        for (i, name) in names.char_indices() {
+            match name {
+                '-' if enable => enable = false,
+                'm' => flags.multiline = enable,
+                'i' => flags.case_insensitive = enable,
+                's' => flags.dot_matches_newline = enable,
+                'x' => flags.ignore_whitespace = enable,
+                'u' => flags.unicode = enable,
+                _ => {
+                    let start = self.offset(input) + i;
+                    let kind = ParsingErrorKind::UnknownFlag(name);
+                    let error = self.error(kind, start, start + name.len_utf8());
+                    self.report(error, ())?;
+                }
+            }
+        }
+
+        Ok((flags, rest))
+    }
+
+    fn close_flag_group(&mut self, input: &'p str) -> Result<(Node, &'p str)> {
+        match input.strip_prefix(')') {
+            Some(rest) => Ok((Node::Empty, rest)),
+            None => {
+                let start = self.offset(input);
+                let kind = ParsingErrorKind::MissingCharacter(')');
+                let error = self.error(kind, start, start);
+                self.report(error, (Node::Empty, input))
+            }
+        }
+    }
+
+    fn fold_case(&self, node: Node) -> Node {
+        if !self.flags.case_insensitive {
+            return node;
+        }
+
+        match node {
+            Node::Character(ch) => match case_variants(ch) {
+                [] => Node::Character(ch),
+                variants => {
+                    let members = std::iter::once(ch).chain(variants.iter().copied());
+                    Node::class(false, members.map(ClassMember::Atom).collect())
+                }
+            },
+            node => node,
+        }
+    }
+
+    fn class(&self, negate: bool, members: Vec<ClassMember>) -> CharacterClass {
+        CharacterClass::with_case_folding(negate, members, self.flags.case_insensitive)
+    }
+
+    fn skip_ignored(&self, input: &'p str) -> &'p str {
+        if !self.flags.ignore_whitespace {
+            return input;
+        }
+
+        let mut rest = input.trim_start();
+
+        while let Some(comment) = rest.strip_prefix('#') {
+            let end = comment.find('\n').map_or(comment.len(), |i| i + 1);
+            rest = comment[end..].trim_start();
+        }
+
+        rest
+    }
+
+    fn wildcard(&self) -> Node {
+        match self.flags.dot_matches_newline {
+            true => Node::Wildcard,
+            false => Node::class(true, vec![ClassMember::Atom('\n')]),
+        }
+    }
+
+    fn word_boundary(&self, negate: bool) -> Assertion {
+        match (self.flags.unicode, negate) {
+            (true, false) => Assertion::WordBoundary,
+            (true, true) => Assertion::NotWordBoundary,
+            (false, false) => Assertion::AsciiWordBoundary,
+            (false, true) => Assertion::AsciiNotWordBoundary,
+        }
+    }
+
+    fn start_assertion(&self) -> Assertion {
+        match self.flags.multiline {
+            true => Assertion::StartOfLine,
+            false => Assertion::StartOfInput,
+        }
+    }
+
+    fn end_assertion(&self) -> Assertion {
+        match self.flags.multiline {
+            true => Assertion::EndOfLine,
+            false => Assertion::EndOfInput,
+        }
+    }
+
+    fn offset(&self, input: &str) -> usize {
+        self.pattern.len() - input.len()
+    }
+
+    fn report<T>(&mut self, error: ParsingError, fallback: T) -> Result<T> {
+        if !self.recover {
+            return Err(error);
+        }
+
+        self.errors.push(error);
+        Ok(fallback)
+    }
+
+    fn error(&self, kind: ParsingErrorKind, start: usize, end: usize) -> ParsingError {
+        ParsingError::new(kind, Span::new(start, end), self.pattern)
     }
+
+    fn end_of_input(&self) -> ParsingError {
+        let end = self.pattern.len();
+        self.error(ParsingErrorKind::UnexpectedEndOfInput, end, end)
+    }
+
+    fn escape_error(&self, start: usize, rest: &str) -> ParsingError {
+        self.error(
+            ParsingErrorKind::InvalidEscapeSequence,
+            start,
+            self.offset(rest),
+        )
+    }
+
+    fn range_error(&self, input: &str, rest: &str) -> ParsingError {
+        let start = self.offset(input) - 1;
+        let end = self.offset(rest) + rest.find('}').map_or(rest.len(), |i| i + 1);
+        self.error(ParsingErrorKind::InvalidRangeQuantifier, start, end)
+    }
+}
+
+fn fold_right(nodes: Vec<Node>, f: impl Fn(Node, Node) -> Node) -> Node {
+    nodes
+        .into_iter()
+        .rev()
+        .reduce(|rhs, lhs| f(lhs, rhs))
+        .unwrap_or(Node::Empty)
+}
+
+fn is_flag_group(input: &str) -> bool {
+    input
+        .strip_prefix('?')
+        .is_some_and(|rest| rest.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '-'))
 }
 
 fn take_while<'a, P>(predicate: P) -> impl Fn(&'a str) -> (&'a str, &'a str) + 'a
@@ -181,8 +803,18 @@ fn take_number(input: &str) -> Result<(Option<usize>, &str)> {
     Ok((number, rest))
 }
 
-fn take_alphabetic(input: &str) -> (&str, &str) {
-    take_while(|ch| ch.is_alphabetic())(input)
+fn take_identifier(input: &str) -> (&str, &str) {
+    match input.starts_with(|ch: char| ch.is_alphabetic() || ch == '_') {
+        true => take_while(|ch| ch.is_alphanumeric() || ch == '_')(input),
+        false => ("", input),
+    }
+}
+
+fn take_class_operator(input: &str) -> Option<(&str, &str)> {
+    ["&&", "--"].into_iter().find_map(|operator| {
+        let operand = input.strip_prefix(operator)?;
+        (!operand.is_empty() && !operand.starts_with(']')).then_some((operator, operand))
+    })
 }
 
 fn take_char(input: &str) -> (Option<char>, &str) {
@@ -192,29 +824,73 @@ fn take_char(input: &str) -> (Option<char>, &str) {
     }
 }
 
-fn needs_escape(ch: char) -> bool {
-    matches!(
-        ch,
-        '\\' | '[' | ']' | '(' | ')' | '{' | '}' | '.' | '?' | '+' | '*' | '-'
-    )
+fn is_escapable(ch: char) -> bool {
+    ch.is_ascii_punctuation() || ch == ' '
 }
 
+const POSIX_CLASSES: &[(&str, &[(char, char)])] = &[
+    ("alnum", &[('0', '9'), ('A', 'Z'), ('a', 'z')]),
+    ("alpha", &[('A', 'Z'), ('a', 'z')]),
+    ("ascii", &[('\0', '\x7F')]),
+    ("blank", &[('\t', '\t'), (' ', ' ')]),
+    ("cntrl", &[('\0', '\x1F'), ('\x7F', '\x7F')]),
+    ("digit", &[('0', '9')]),
+    ("graph", &[('!', '~')]),
+    ("lower", &[('a', 'z')]),
+    ("print", &[(' ', '~')]),
+    ("punct", &[('!', '/'), (':', '@'), ('[', '`'), ('{', '~')]),
+    ("space", &[('\t', '\r'), (' ', ' ')]),
+    ("upper", &[('A', 'Z')]),
+    ("word", &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')]),
+    ("xdigit", &[('0', '9'), ('A', 'F'), ('a', 'f')]),
+];
+
 #[inline]
-fn digit_range() -> Vec<ClassMember> {
-    vec![ClassMember::Range('0', '9')]
+fn perl_class(name: &'static str, ranges: &'static [(char, char)]) -> Vec<ClassMember> {
+    vec![ClassMember::Property(UnicodeProperty {
+        name,
+        negate: false,
+        ranges,
+    })]
 }
 
 #[inline]
-fn word_range() -> Vec<ClassMember> {
-    vec![
-        ClassMember::Range('0', '9'),
-        ClassMember::Range('a', 'z'),
-        ClassMember::Range('A', 'Z'),
-    ]
+fn digit_range(unicode: bool) -> Vec<ClassMember> {
+    match unicode {
+        true => perl_class("digit", PERL_DIGIT),
+        false => vec![ClassMember::Range('0', '9')],
+    }
+}
+
+pub const WORD_MEMBERS: &[ClassMember] = &[
+    ClassMember::Range('0', '9'),
+    ClassMember::Range('a', 'z'),
+    ClassMember::Range('A', 'Z'),
+    ClassMember::Atom('_'),
+];
+
+#[inline]
+fn word_range(unicode: bool) -> Vec<ClassMember> {
+    match unicode {
+        true => perl_class("word", PERL_WORD),
+        false => WORD_MEMBERS.to_vec(),
+    }
+}
+
+pub fn is_ascii_word_char(ch: char) -> bool {
+    WORD_MEMBERS.iter().any(|member| member.contains(&ch))
+}
+
+pub fn is_word_char(ch: char) -> bool {
+    is_ascii_word_char(ch) || (!ch.is_ascii() && in_ranges(PERL_WORD, ch))
 }
 
 #[inline]
-fn whitespace() -> Vec<ClassMember> {
+fn whitespace(unicode: bool) -> Vec<ClassMember> {
+    if unicode {
+        return perl_class("space", PERL_SPACE);
+    }
+
     vec![
         ClassMember::Atom(' '),
         ClassMember::Atom('\t'),
@@ -225,25 +901,41 @@ fn whitespace() -> Vec<ClassMember> {
     ]
 }
 
-fn get_range_alias(ch: char) -> Option<Node> {
-    match ch {
-        'd' => Some(Node::class(false, digit_range())),
-        'D' => Some(Node::class(true, digit_range())),
-        'w' => Some(Node::class(false, word_range())),
-        'W' => Some(Node::class(true, word_range())),
-        's' => Some(Node::class(false, whitespace())),
-        'S' => Some(Node::class(true, whitespace())),
-        _ => None,
-    }
+fn get_range_alias(ch: char, flags: Flags) -> Option<CharacterClass> {
+    let (negate, members) = match ch {
+        'd' => (false, digit_range(flags.unicode)),
+        'D' => (true, digit_range(flags.unicode)),
+        'w' => (false, word_range(flags.unicode)),
+        'W' => (true, word_range(flags.unicode)),
+        's' => (false, whitespace(flags.unicode)),
+        'S' => (true, whitespace(flags.unicode)),
+        _ => return None,
+    };
+
+    Some(CharacterClass::with_case_folding(
+        negate,
+        members,
+        flags.case_insensitive,
+    ))
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        ast::{ClassMember, Node, Range},
-        parser::parse_regex,
+        ast::{Assertion, CharacterClass, ClassMember, Node, Range, UnicodeProperty},
+        error::{ParsingErrorKind, Span},
+        parser::{
+            parse_regex, parse_regex_recovering, parse_regex_with_config, parse_regex_with_flags,
+            Flags, ParserConfig,
+        },
+        unicode_tables::PERL_WORD,
     };
 
+    fn error(regex: &str) -> (ParsingErrorKind, Span) {
+        let error = parse_regex(regex).unwrap_err();
+        (error.kind, error.span)
+    }
+
     #[test]
     fn test_chars() {
         let ast = parse_regex("ok!").unwrap();
@@ -266,6 +958,28 @@ mod tests {
         assert_eq!(ast, expected);
     }
 
+    #[test]
+    fn test_lazy_quantifiers() {
+        let ast = parse_regex("a*?b+?c??").unwrap();
+        let expected = Node::concatenation(
+            Node::star(Node::Character('a')).lazy(),
+            Node::concatenation(
+                Node::plus(Node::Character('b')).lazy(),
+                Node::optional(Node::Character('c')).lazy(),
+            ),
+        );
+
+        assert_eq!(ast, expected);
+
+        let ast = parse_regex("a{2,}?").unwrap();
+        let expected = Node::LazyRange {
+            inner: Box::new(Node::Character('a')),
+            range: Range::new(2, None),
+        };
+
+        assert_eq!(ast, expected);
+    }
+
     #[test]
     fn test_alternation() {
         let ast = parse_regex("la|le").unwrap();
@@ -275,6 +989,26 @@ mod tests {
         );
 
         assert_eq!(ast, expected);
+
+        let ast = parse_regex("a||b").unwrap();
+        let expected = Node::alternation(
+            Node::Character('a'),
+            Node::alternation(Node::Empty, Node::Character('b')),
+        );
+
+        assert_eq!(ast, expected);
+        assert_eq!(
+            parse_regex("(|a)").unwrap(),
+            Node::group(
+                Node::alternation(Node::Empty, Node::Character('a')),
+                true,
+                None
+            )
+        );
+        assert_eq!(
+            parse_regex("|a").unwrap(),
+            Node::alternation(Node::Empty, Node::Character('a'))
+        );
     }
 
     #[test]
@@ -292,6 +1026,72 @@ mod tests {
         assert_eq!(ast, expected);
     }
 
+    #[test]
+    fn test_anchors() {
+        let ast = parse_regex(r#"^a\$$"#).unwrap();
+        let expected = Node::concatenation(
+            Node::Assertion(Assertion::StartOfInput),
+            Node::concatenation(
+                Node::Character('a'),
+                Node::concatenation(Node::Character('$'), Node::Assertion(Assertion::EndOfInput)),
+            ),
+        );
+
+        assert_eq!(ast, expected);
+    }
+
+    #[test]
+    fn test_word_boundary() {
+        let ast = parse_regex(r#"\ba\B"#).unwrap();
+        let expected = Node::concatenation(
+            Node::Assertion(Assertion::WordBoundary),
+            Node::concatenation(
+                Node::Character('a'),
+                Node::Assertion(Assertion::NotWordBoundary),
+            ),
+        );
+
+        assert_eq!(ast, expected);
+    }
+
+    #[test]
+    fn test_multiline_flag() {
+        let flags = Flags {
+            multiline: true,
+            ..Default::default()
+        };
+        let expected = Node::concatenation(
+            Node::Assertion(Assertion::StartOfLine),
+            Node::Assertion(Assertion::EndOfLine),
+        );
+
+        assert_eq!(parse_regex_with_flags("^$", flags).unwrap(), expected);
+
+        let ast = parse_regex("(?m)^(?-m)^").unwrap();
+        let expected = Node::concatenation(
+            Node::Empty,
+            Node::concatenation(
+                Node::Assertion(Assertion::StartOfLine),
+                Node::concatenation(Node::Empty, Node::Assertion(Assertion::StartOfInput)),
+            ),
+        );
+
+        assert_eq!(ast, expected);
+
+        let ast = parse_regex("((?m)^)^").unwrap();
+        let expected = Node::concatenation(
+            Node::group(
+                Node::concatenation(Node::Empty, Node::Assertion(Assertion::StartOfLine)),
+                true,
+                None,
+            ),
+            Node::Assertion(Assertion::StartOfInput),
+        );
+
+        assert_eq!(ast, expected);
+        assert!(parse_regex("(?q)").is_err());
+    }
+
     #[test]
     fn test_range_quantifier() {
         let ast = parse_regex("1{2,5}").unwrap();
@@ -358,7 +1158,7 @@ mod tests {
 
         assert_eq!(ast, expected);
 
-        let ast = parse_regex("(:?foo)bar").unwrap();
+        let ast = parse_regex("(?:foo)bar").unwrap();
         let expected = Node::concatenation(
             Node::group(
                 Node::concatenation(
@@ -393,5 +1193,479 @@ mod tests {
         );
 
         assert_eq!(ast, expected);
+        assert_eq!(parse_regex("(?P<capt>foo)bar").unwrap(), expected);
+        assert_eq!(parse_regex("(?'capt'foo)bar").unwrap(), expected);
+        assert_eq!(
+            parse_regex("(?<_field_12>a)").unwrap(),
+            Node::group(Node::Character('a'), true, Some("_field_12"))
+        );
+    }
+
+    #[test]
+    fn test_legacy_non_capturing() {
+        let expected = Node::group(Node::Character('a'), false, None);
+        let config = ParserConfig {
+            legacy_non_capturing: true,
+            ..Default::default()
+        };
+
+        assert_eq!(parse_regex_with_config("(:?a)", config).unwrap(), expected);
+        assert_eq!(parse_regex_with_config("(?:a)", config).unwrap(), expected);
+        assert_eq!(
+            parse_regex("(:?a)").unwrap(),
+            Node::group(
+                Node::concatenation(Node::optional(Node::Character(':')), Node::Character('a')),
+                true,
+                None,
+            )
+        );
+    }
+
+    #[test]
+    fn test_escape_sequences() {
+        let chars = |regex: &str| {
+            let mut chars = Vec::new();
+            let mut node = parse_regex(regex).unwrap();
+
+            loop {
+                match node {
+                    Node::Concatenation(lhs, rhs) => {
+                        chars.push(*lhs);
+                        node = *rhs;
+                    }
+                    node => {
+                        chars.push(node);
+                        break chars;
+                    }
+                }
+            }
+        };
+        let expected: Vec<_> = "\n\t\r\0AAé😀|/<".chars().map(Node::Character).collect();
+
+        assert_eq!(chars(r"\n\t\r\0\x41\x{41}\u00E9\u{1F600}\|\/\<"), expected);
+        assert_eq!(
+            parse_regex(r"[\t\x30-\x39\]]").unwrap(),
+            Node::class(
+                false,
+                vec![
+                    ClassMember::Atom('\t'),
+                    ClassMember::Range('0', '9'),
+                    ClassMember::Atom(']'),
+                ],
+            )
+        );
+    }
+
+    #[test]
+    fn test_error_spans() {
+        assert_eq!(
+            error(r"ab\q"),
+            (ParsingErrorKind::InvalidEscapeSequence, Span::new(2, 4))
+        );
+        assert_eq!(
+            error(r"[a\q]"),
+            (ParsingErrorKind::InvalidEscapeSequence, Span::new(2, 4))
+        );
+        assert_eq!(
+            error("a(b(c)"),
+            (ParsingErrorKind::MissingCharacter(')'), Span::new(1, 2))
+        );
+        assert_eq!(
+            error("a{2,x}"),
+            (ParsingErrorKind::InvalidRangeQuantifier, Span::new(1, 6))
+        );
+        assert_eq!(
+            error("[z-a]"),
+            (ParsingErrorKind::RangeOutOfOrder, Span::new(1, 4))
+        );
+        assert_eq!(
+            error("(?<1>a)"),
+            (ParsingErrorKind::InvalidCaptureName, Span::new(2, 5))
+        );
+        assert_eq!(
+            error("(?P<a-b>c)"),
+            (ParsingErrorKind::InvalidCaptureName, Span::new(3, 8))
+        );
+        assert_eq!(
+            error("(?'name>a)"),
+            (ParsingErrorKind::InvalidCaptureName, Span::new(2, 7))
+        );
+        assert_eq!(
+            error("(?<id>a)|(?P<id>b)"),
+            (
+                ParsingErrorKind::DuplicateCaptureName("id".to_string()),
+                Span::new(13, 15)
+            )
+        );
+        assert_eq!(
+            error("a(?=b)"),
+            (ParsingErrorKind::UnexpectedCharacter('='), Span::new(2, 4))
+        );
+        assert_eq!(
+            error("(?!b)"),
+            (ParsingErrorKind::UnexpectedCharacter('!'), Span::new(1, 3))
+        );
+        assert_eq!(
+            error("(?<=b)a"),
+            (ParsingErrorKind::UnexpectedCharacter('='), Span::new(1, 4))
+        );
+        assert_eq!(
+            error("(?<!b)a"),
+            (ParsingErrorKind::UnexpectedCharacter('!'), Span::new(1, 4))
+        );
+        assert_eq!(
+            error("(?mq)"),
+            (ParsingErrorKind::UnknownFlag('q'), Span::new(3, 4))
+        );
+        assert_eq!(
+            error("[ab"),
+            (ParsingErrorKind::UnexpectedEndOfInput, Span::new(3, 3))
+        );
+        assert_eq!(
+            error(r"a\x4"),
+            (ParsingErrorKind::InvalidEscapeSequence, Span::new(1, 4))
+        );
+        assert_eq!(
+            error(r"\u{D800}a"),
+            (ParsingErrorKind::InvalidEscapeSequence, Span::new(0, 8))
+        );
+        assert_eq!(
+            error(r"[\xZZ]"),
+            (ParsingErrorKind::InvalidEscapeSequence, Span::new(1, 3))
+        );
+        assert_eq!(
+            error(r"a\p{Klingon}"),
+            (
+                ParsingErrorKind::UnknownUnicodeProperty("Klingon".to_string()),
+                Span::new(1, 12)
+            )
+        );
+        assert_eq!(
+            error(r"a\p {L}"),
+            (ParsingErrorKind::InvalidEscapeSequence, Span::new(1, 3))
+        );
+        assert_eq!(
+            error(r"\p{}"),
+            (ParsingErrorKind::InvalidEscapeSequence, Span::new(0, 2))
+        );
+        assert_eq!(
+            error(r"[\p{L]"),
+            (ParsingErrorKind::MissingCharacter('}'), Span::new(1, 6))
+        );
+    }
+
+    #[test]
+    fn test_unicode_properties() {
+        let property = |ast: Node| match ast {
+            Node::CharacterClass(class) => match class.members[..] {
+                [ClassMember::Property(property)] => (class.negate, property.name, property.negate),
+                _ => panic!("expected a single property"),
+            },
+            _ => panic!("expected a character class"),
+        };
+
+        assert_eq!(property(parse_regex(r"\pL").unwrap()), (false, "l", false));
+        assert_eq!(
+            property(parse_regex(r"\p{Greek}").unwrap()),
+            (false, "greek", false)
+        );
+        assert_eq!(
+            property(parse_regex(r"\P{gc=Decimal_Number}").unwrap()),
+            (false, "decimalnumber", true)
+        );
+        assert_eq!(
+            property(parse_regex(r"[^\P{N}]").unwrap()),
+            (true, "n", true)
+        );
+    }
+
+    #[test]
+    fn test_perl_classes() {
+        let ast = parse_regex(r"(?-u:\w\b)\W\B").unwrap();
+        let expected = Node::concatenation(
+            Node::group(
+                Node::concatenation(
+                    Node::class(
+                        false,
+                        vec![
+                            ClassMember::Range('0', '9'),
+                            ClassMember::Range('a', 'z'),
+                            ClassMember::Range('A', 'Z'),
+                            ClassMember::Atom('_'),
+                        ],
+                    ),
+                    Node::Assertion(Assertion::AsciiWordBoundary),
+                ),
+                false,
+                None,
+            ),
+            Node::concatenation(
+                Node::class(
+                    true,
+                    vec![ClassMember::Property(UnicodeProperty {
+                        name: "word",
+                        negate: false,
+                        ranges: PERL_WORD,
+                    })],
+                ),
+                Node::Assertion(Assertion::NotWordBoundary),
+            ),
+        );
+
+        assert_eq!(ast, expected);
+
+        let flags = Flags {
+            unicode: false,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            parse_regex_with_flags(r"\d", flags).unwrap(),
+            Node::class(false, vec![ClassMember::Range('0', '9')])
+        );
+    }
+
+    #[test]
+    fn test_nested_shorthands() {
+        let flags = Flags {
+            unicode: false,
+            ..Default::default()
+        };
+        let ast = parse_regex_with_flags(r"[\d_\S-]", flags).unwrap();
+        let expected = Node::class(
+            false,
+            vec![
+                ClassMember::Class(CharacterClass::new(
+                    false,
+                    vec![ClassMember::Range('0', '9')],
+                )),
+                ClassMember::Atom('_'),
+                ClassMember::Class(CharacterClass::new(
+                    true,
+                    vec![
+                        ClassMember::Atom(' '),
+                        ClassMember::Atom('\t'),
+                        ClassMember::Atom('\n'),
+                        ClassMember::Atom('\r'),
+                        ClassMember::Atom('\x0C'),
+                        ClassMember::Atom('\x0B'),
+                    ],
+                )),
+                ClassMember::Atom('-'),
+            ],
+        );
+
+        assert_eq!(ast, expected);
+    }
+
+    #[test]
+    fn test_class_set_operations() {
+        let ast = parse_regex("[a-z&&[^aeiou]--x]").unwrap();
+        let vowels = "aeiou".chars().map(ClassMember::Atom).collect();
+        let consonants = ClassMember::Intersection(
+            CharacterClass::new(false, vec![ClassMember::Range('a', 'z')]),
+            CharacterClass::new(
+                false,
+                vec![ClassMember::Class(CharacterClass::new(true, vowels))],
+            ),
+        );
+        let expected = Node::class(
+            false,
+            vec![ClassMember::Difference(
+                CharacterClass::new(false, vec![consonants]),
+                CharacterClass::new(false, vec![ClassMember::Atom('x')]),
+            )],
+        );
+
+        assert_eq!(ast, expected);
+        assert_eq!(
+            parse_regex("[!--]").unwrap(),
+            Node::class(false, vec![ClassMember::Range('!', '-')])
+        );
+        assert_eq!(
+            parse_regex("[a&&]").unwrap(),
+            Node::class(
+                false,
+                vec![
+                    ClassMember::Atom('a'),
+                    ClassMember::Atom('&'),
+                    ClassMember::Atom('&'),
+                ],
+            )
+        );
+        assert_eq!(
+            error("[a&&[b]"),
+            (ParsingErrorKind::UnexpectedEndOfInput, Span::new(7, 7))
+        );
+    }
+
+    #[test]
+    fn test_posix_classes() {
+        let ast = parse_regex(r"[[:^digit:]x\[:]").unwrap();
+        let expected = Node::class(
+            false,
+            vec![
+                ClassMember::Property(UnicodeProperty {
+                    name: "digit",
+                    negate: true,
+                    ranges: &[('0', '9')],
+                }),
+                ClassMember::Atom('x'),
+                ClassMember::Atom('['),
+                ClassMember::Atom(':'),
+            ],
+        );
+
+        assert_eq!(ast, expected);
+        assert_eq!(
+            error("a[[:alfa:]]"),
+            (ParsingErrorKind::InvalidCharacterClass, Span::new(2, 10))
+        );
+    }
+
+    #[test]
+    fn test_recovering_parse() {
+        let (ast, errors) = parse_regex_recovering(r"(a\q|b{2,x}", ParserConfig::default());
+        let expected = Node::group(
+            Node::alternation(
+                Node::concatenation(Node::Character('a'), Node::Character('q')),
+                Node::Character('b'),
+            ),
+            true,
+            None,
+        );
+        let errors: Vec<_> = errors.into_iter().map(|e| (e.kind, e.span)).collect();
+
+        assert_eq!(ast, expected);
+        assert_eq!(
+            errors,
+            vec![
+                (ParsingErrorKind::InvalidEscapeSequence, Span::new(2, 4)),
+                (ParsingErrorKind::InvalidRangeQuantifier, Span::new(6, 11)),
+                (ParsingErrorKind::MissingCharacter(')'), Span::new(0, 1)),
+            ]
+        );
+
+        let (ast, errors) = parse_regex_recovering("a)[z-a", ParserConfig::default());
+        let expected = Node::concatenation(Node::Character('a'), Node::class(false, vec![]));
+        let errors: Vec<_> = errors.into_iter().map(|e| e.kind).collect();
+
+        assert_eq!(ast, expected);
+        assert_eq!(
+            errors,
+            vec![
+                ParsingErrorKind::UnexpectedCharacter(')'),
+                ParsingErrorKind::RangeOutOfOrder,
+                ParsingErrorKind::UnexpectedEndOfInput,
+            ]
+        );
+        assert!(parse_regex("a)b").is_err());
+    }
+
+    #[test]
+    fn test_case_insensitive_flag() {
+        let ast = parse_regex("(?i)a1").unwrap();
+        let expected = Node::concatenation(
+            Node::Empty,
+            Node::concatenation(
+                Node::class(false, vec![ClassMember::Atom('a'), ClassMember::Atom('A')]),
+                Node::Character('1'),
+            ),
+        );
+
+        assert_eq!(ast, expected);
+
+        let ast = parse_regex("(?i:[^x])x").unwrap();
+        let expected = Node::concatenation(
+            Node::group(
+                Node::CharacterClass(CharacterClass::with_case_folding(
+                    true,
+                    vec![ClassMember::Atom('x')],
+                    true,
+                )),
+                false,
+                None,
+            ),
+            Node::Character('x'),
+        );
+
+        assert_eq!(ast, expected);
+    }
+
+    #[test]
+    fn test_dot_matches_newline_flag() {
+        let any_but_newline = Node::class(true, vec![ClassMember::Atom('\n')]);
+
+        assert_eq!(parse_regex(".").unwrap(), any_but_newline);
+        assert_eq!(
+            parse_regex("(?s:.).").unwrap(),
+            Node::concatenation(Node::group(Node::Wildcard, false, None), any_but_newline)
+        );
+        assert_eq!(
+            parse_regex("(?s)(?-s).").unwrap(),
+            parse_regex("(?i)(?-i).").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_ignore_whitespace() {
+        let flags = Flags {
+            ignore_whitespace: true,
+            ..Default::default()
+        };
+        let ast = parse_regex_with_flags("a + # one or more\n [ b]", flags).unwrap();
+        let expected = Node::concatenation(
+            Node::plus(Node::Character('a')),
+            Node::class(false, vec![ClassMember::Atom(' '), ClassMember::Atom('b')]),
+        );
+
+        assert_eq!(ast, expected);
+    }
+
+    #[test]
+    fn test_extended_flag() {
+        let ast = parse_regex("(?x: a \\  b # comment\n )[ #]\\#").unwrap();
+        let expected = Node::concatenation(
+            Node::group(
+                Node::concatenation(
+                    Node::Character('a'),
+                    Node::concatenation(Node::Character(' '), Node::Character('b')),
+                ),
+                false,
+                None,
+            ),
+            Node::concatenation(
+                Node::class(false, vec![ClassMember::Atom(' '), ClassMember::Atom('#')]),
+                Node::Character('#'),
+            ),
+        );
+
+        assert_eq!(ast, expected);
+        assert_eq!(
+            parse_regex("(?x)a b|  c # comment").unwrap(),
+            parse_regex("(?x)ab|c").unwrap()
+        );
+        assert_eq!(
+            parse_regex("(?x)\\p {Greek} a{2, 3} b{ 1 , } \\P L").unwrap(),
+            parse_regex("(?x)\\p{Greek}a{2,3}b{1,}\\PL").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_nest_limit() {
+        let config = ParserConfig {
+            nest_limit: 2,
+            ..Default::default()
+        };
+        let error = parse_regex_with_config("((a)(b(c)))", config).unwrap_err();
+
+        assert_eq!(error.kind, ParsingErrorKind::NestingLimitExceeded(2));
+        assert_eq!(error.span, Span::new(6, 7));
+        assert!(parse_regex_with_config("((a)(b))", config).is_ok());
+
+        let error = parse_regex_with_config("[[[a]]]", config).unwrap_err();
+
+        assert_eq!(error.kind, ParsingErrorKind::NestingLimitExceeded(2));
+        assert_eq!(error.span, Span::new(2, 3));
     }
 }
@@ -0,0 +1,415 @@
+use crate::{
+    nfa::{CaptureGroup, Context, StateId, START},
+    program::{Program, SparseSet},
+};
+use std::collections::HashMap;
+
+pub type Slots = Vec<Option<usize>>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatchSemantics {
+    #[default]
+    LeftmostFirst,
+    LeftmostLongest,
+}
+
+#[derive(Debug, Default)]
+pub struct SlotMap {
+    group_count: usize,
+    starts: HashMap<StateId, Vec<usize>>,
+    ends: HashMap<StateId, Vec<usize>>,
+}
+
+impl SlotMap {
+    pub fn new(group_count: usize) -> Self {
+        Self {
+            group_count,
+            ..Default::default()
+        }
+    }
+
+    pub fn insert(&mut self, group: usize, capture: &CaptureGroup) {
+        self.starts.entry(capture.start).or_default().push(group);
+        self.ends.entry(capture.end).or_default().push(group);
+    }
+
+    pub fn slot_count(&self) -> usize {
+        self.group_count * 2
+    }
+
+    pub fn slots(&self, state: StateId) -> impl Iterator<Item = usize> + '_ {
+        let starts = self.starts.get(&state).into_iter().flatten();
+        let ends = self.ends.get(&state).into_iter().flatten();
+
+        starts
+            .map(|group| group * 2)
+            .chain(ends.map(|group| group * 2 + 1))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Frame {
+    Explore(StateId),
+    Restore(usize, Option<usize>),
+}
+
+#[derive(Debug)]
+struct ThreadList {
+    set: SparseSet,
+    slots: Slots,
+}
+
+impl ThreadList {
+    fn new(state_count: usize, slot_count: usize) -> Self {
+        Self {
+            set: SparseSet::new(state_count),
+            slots: vec![None; state_count * slot_count],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PikeVmCache {
+    current: ThreadList,
+    next: ThreadList,
+    stack: Vec<Frame>,
+    scratch: Vec<Option<usize>>,
+}
+
+impl PikeVmCache {
+    fn new(state_count: usize, slot_count: usize) -> Self {
+        Self {
+            current: ThreadList::new(state_count, slot_count),
+            next: ThreadList::new(state_count, slot_count),
+            stack: Vec::new(),
+            scratch: vec![None; slot_count],
+        }
+    }
+
+    pub fn slot_count(&self) -> usize {
+        self.scratch.len()
+    }
+
+    fn clear(&mut self) {
+        self.current.set.clear();
+        self.next.set.clear();
+        self.stack.clear();
+    }
+}
+
+#[derive(Debug)]
+pub struct PikeVm<'a> {
+    program: &'a Program,
+    semantics: MatchSemantics,
+    slot_count: usize,
+}
+
+impl<'a> PikeVm<'a> {
+    pub fn new(program: &'a Program, semantics: MatchSemantics, slot_count: usize) -> Self {
+        Self {
+            program,
+            semantics,
+            slot_count: slot_count.min(program.slot_count).max(2),
+        }
+    }
+
+    pub fn slot_count(&self) -> usize {
+        self.slot_count
+    }
+
+    pub fn cache(&self) -> PikeVmCache {
+        PikeVmCache::new(self.program.len(), self.slot_count)
+    }
+
+    pub fn search(
+        &self,
+        cache: &mut PikeVmCache,
+        input: &str,
+        start: usize,
+        end: usize,
+    ) -> Option<Slots> {
+        cache.clear();
+
+        let PikeVmCache {
+            current,
+            next,
+            stack,
+            scratch,
+        } = cache;
+        let mut result = None;
+        let chars = input[start..end]
+            .char_indices()
+            .map(|(i, ch)| (start + i, Some(ch)))
+            .chain([(end, None)]);
+
+        for (position, ch) in chars {
+            let context = Context::new(input, position);
+
+            if result.is_none() {
+                scratch.fill(None);
+                scratch[0] = Some(position);
+                self.add_thread(current, stack, scratch, START, &context, position);
+            }
+
+            self.accept(current, position, &mut result);
+
+            let Some(ch) = ch else {
+                break;
+            };
+
+            let next_position = position + ch.len_utf8();
+            let next_context = Context::new(input, next_position);
+
+            for state in current.set.iter() {
+                let range = state * self.slot_count..(state + 1) * self.slot_count;
+
+                for end in self.program.next(state, ch) {
+                    scratch.copy_from_slice(&current.slots[range.clone()]);
+                    self.add_thread(next, stack, scratch, end, &next_context, next_position);
+                }
+            }
+
+            if next.set.is_empty() && result.is_some() {
+                break;
+            }
+
+            std::mem::swap(current, next);
+            next.set.clear();
+        }
+
+        result
+    }
+
+    pub fn is_match(&self, input: &str) -> bool {
+        let mut current = SparseSet::new(self.program.len());
+        let mut next = SparseSet::new(self.program.len());
+        let mut stack = Vec::new();
+        let positions = input
+            .char_indices()
+            .map(|(i, ch)| (i, Some(ch)))
+            .chain([(input.len(), None)]);
+
+        for (position, ch) in positions {
+            let context = Context::new(input, position);
+
+            stack.push(START);
+
+            while let Some(state) = stack.pop() {
+                if current.insert(state) {
+                    stack.extend(self.program.epsilons(state, &context));
+                }
+            }
+
+            if current.contains(self.program.accept) {
+                return true;
+            }
+
+            let Some(ch) = ch else {
+                break;
+            };
+
+            let next_context = Context::new(input, position + ch.len_utf8());
+
+            for state in current.iter() {
+                for end in self.program.next(state, ch) {
+                    stack.push(end);
+
+                    while let Some(state) = stack.pop() {
+                        if next.insert(state) {
+                            stack.extend(self.program.epsilons(state, &next_context));
+                        }
+                    }
+                }
+            }
+
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+        }
+
+        false
+    }
+
+    fn add_thread(
+        &self,
+        list: &mut ThreadList,
+        stack: &mut Vec<Frame>,
+        scratch: &mut [Option<usize>],
+        state: StateId,
+        context: &Context,
+        position: usize,
+    ) {
+        stack.push(Frame::Explore(state));
+
+        while let Some(frame) = stack.pop() {
+            let state = match frame {
+                Frame::Explore(state) => state,
+                Frame::Restore(slot, value) => {
+                    scratch[slot] = value;
+                    continue;
+                }
+            };
+
+            if !list.set.insert(state) {
+                continue;
+            }
+
+            for &slot in &self.program.states[state].slots {
+                if slot < self.slot_count {
+                    stack.push(Frame::Restore(slot, scratch[slot]));
+                    scratch[slot] = Some(position);
+                }
+            }
+
+            let offset = state * self.slot_count;
+            list.slots[offset..offset + self.slot_count].copy_from_slice(scratch);
+
+            for end in self.program.epsilons(state, context).rev() {
+                stack.push(Frame::Explore(end));
+            }
+        }
+    }
+
+    fn accept(&self, list: &mut ThreadList, position: usize, result: &mut Option<Slots>) {
+        let Some(index) = list.set.iter().position(|s| s == self.program.accept) else {
+            return;
+        };
+
+        let offset = self.program.accept * self.slot_count;
+        let slots = &list.slots[offset..offset + self.slot_count];
+        let start = slots[0];
+
+        match result {
+            Some(result) => result.copy_from_slice(slots),
+            None => *result = Some(slots.to_vec()),
+        }
+
+        if let Some(result) = result {
+            result[1] = Some(position);
+        }
+
+        match self.semantics {
+            MatchSemantics::LeftmostFirst => list.set.truncate(index + 1),
+            MatchSemantics::LeftmostLongest => {
+                let slot_count = self.slot_count;
+                let slots = &list.slots;
+
+                list.set.retain(|state| slots[state * slot_count] <= start);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nfa::Nfa, parser::parse_regex};
+
+    fn search(regex: &str, input: &str, semantics: MatchSemantics) -> Option<Slots> {
+        search_until(regex, input, input.len(), semantics)
+    }
+
+    fn search_until(
+        regex: &str,
+        input: &str,
+        end: usize,
+        semantics: MatchSemantics,
+    ) -> Option<Slots> {
+        let nfa = Nfa::from(parse_regex(regex).unwrap());
+        let mut slot_map = SlotMap::new(nfa.capture_groups.len() + 1);
+
+        for (index, group) in nfa.capture_groups.iter().enumerate() {
+            slot_map.insert(index + 1, group);
+        }
+
+        let program = Program::new(&nfa, &slot_map);
+
+        let vm = PikeVm::new(&program, semantics, slot_map.slot_count());
+
+        vm.search(&mut vm.cache(), input, 0, end)
+    }
+
+    fn end(regex: &str, input: &str, semantics: MatchSemantics) -> Option<usize> {
+        search(regex, input, semantics).and_then(|slots| slots[1])
+    }
+
+    #[test]
+    fn test_thread_priority() {
+        assert_eq!(end("a|ab", "ab", MatchSemantics::LeftmostFirst), Some(1));
+        assert_eq!(end("ab|a", "ab", MatchSemantics::LeftmostFirst), Some(2));
+        assert_eq!(end("a|ab", "ab", MatchSemantics::LeftmostLongest), Some(2));
+        assert_eq!(end("a+?", "aaa", MatchSemantics::LeftmostFirst), Some(1));
+        assert_eq!(end("a+", "aaa", MatchSemantics::LeftmostFirst), Some(3));
+        assert_eq!(end("a+", "b", MatchSemantics::LeftmostFirst), None);
+    }
+
+    #[test]
+    fn test_unanchored_search() {
+        let slots = search("b+", "aabbba", MatchSemantics::LeftmostFirst).unwrap();
+
+        assert_eq!(slots, vec![Some(2), Some(5)]);
+
+        let slots = search("abcd|bc", "abcd", MatchSemantics::LeftmostLongest).unwrap();
+
+        assert_eq!(slots, vec![Some(0), Some(4)]);
+
+        let slots = search("bc|abcd", "xabcd", MatchSemantics::LeftmostFirst).unwrap();
+
+        assert_eq!(slots, vec![Some(1), Some(5)]);
+        assert_eq!(search("ab", "aaaa", MatchSemantics::LeftmostFirst), None);
+    }
+
+    #[test]
+    fn test_bounded_search() {
+        let slots = search_until("b+", "abbbb", 3, MatchSemantics::LeftmostFirst).unwrap();
+
+        assert_eq!(slots, vec![Some(1), Some(3)]);
+        assert_eq!(
+            search_until("b$", "abbb", 3, MatchSemantics::LeftmostFirst),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cache_reuse() {
+        let nfa = Nfa::from(parse_regex("(a)|b").unwrap());
+        let mut slot_map = SlotMap::new(2);
+
+        slot_map.insert(1, &nfa.capture_groups[0]);
+
+        let program = Program::new(&nfa, &slot_map);
+        let vm = PikeVm::new(
+            &program,
+            MatchSemantics::LeftmostFirst,
+            slot_map.slot_count(),
+        );
+        let mut cache = vm.cache();
+
+        assert_eq!(
+            vm.search(&mut cache, "xa", 0, 2),
+            Some(vec![Some(1), Some(2), Some(1), Some(2)])
+        );
+        assert_eq!(
+            vm.search(&mut cache, "b", 0, 1),
+            Some(vec![Some(0), Some(1), None, None])
+        );
+        assert_eq!(vm.search(&mut cache, "xa", 2, 2), None);
+    }
+
+    #[test]
+    fn test_thread_slots() {
+        let slots = search("(a|(b))+", "ab", MatchSemantics::LeftmostFirst).unwrap();
+
+        assert_eq!(
+            slots,
+            vec![Some(0), Some(2), Some(1), Some(2), Some(1), Some(2)]
+        );
+
+        let slots = search("(a)?b", "b", MatchSemantics::LeftmostFirst).unwrap();
+
+        assert_eq!(slots, vec![Some(0), Some(1), None, None]);
+
+        let slots = search("(a)*b", "aab", MatchSemantics::LeftmostFirst).unwrap();
+
+        assert_eq!(slots, vec![Some(0), Some(3), Some(1), Some(2)]);
+    }
+}
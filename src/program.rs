@@ -0,0 +1,168 @@
+use crate::{
+    nfa::{Context, Nfa, StateId, Transition},
+    pikevm::SlotMap,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct State {
+    pub epsilons: Vec<Transition>,
+    pub transitions: Vec<Transition>,
+    pub slots: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Program {
+    pub states: Vec<State>,
+    pub accept: StateId,
+    pub slot_count: usize,
+}
+
+impl Program {
+    pub fn new(nfa: &Nfa, slot_map: &SlotMap) -> Self {
+        let mut states = vec![State::default(); nfa.state_count];
+
+        for (&from, transitions) in &nfa.transitions {
+            for transition in transitions {
+                match transition.is_consuming() {
+                    true => states[from].transitions.push(transition.clone()),
+                    false => states[from].epsilons.push(transition.clone()),
+                }
+            }
+        }
+
+        for (id, state) in states.iter_mut().enumerate() {
+            state.slots.extend(slot_map.slots(id));
+        }
+
+        Self {
+            states,
+            accept: nfa.state_count - 1,
+            slot_count: slot_map.slot_count(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn epsilons<'a>(
+        &'a self,
+        state: StateId,
+        context: &'a Context,
+    ) -> impl DoubleEndedIterator<Item = StateId> + 'a {
+        self.states[state]
+            .epsilons
+            .iter()
+            .filter_map(|t| t.is_epsilon(context).then_some(t.end))
+    }
+
+    pub fn next(&self, state: StateId, input: char) -> impl Iterator<Item = StateId> + '_ {
+        self.states[state]
+            .transitions
+            .iter()
+            .filter_map(move |t| t.accept(&input).then_some(t.end))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SparseSet {
+    dense: Vec<StateId>,
+    sparse: Vec<usize>,
+}
+
+impl SparseSet {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            dense: Vec::with_capacity(capacity),
+            sparse: vec![0; capacity],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
+    pub fn contains(&self, value: StateId) -> bool {
+        let index = self.sparse[value];
+        index < self.dense.len() && self.dense[index] == value
+    }
+
+    pub fn insert(&mut self, value: StateId) -> bool {
+        if self.contains(value) {
+            return false;
+        }
+
+        self.sparse[value] = self.dense.len();
+        self.dense.push(value);
+
+        true
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = StateId> + '_ {
+        self.dense.iter().copied()
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.dense.truncate(len);
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(StateId) -> bool) {
+        self.dense.retain(|&value| f(value));
+
+        for (index, &value) in self.dense.iter().enumerate() {
+            self.sparse[value] = index;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.dense.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_regex;
+
+    #[test]
+    fn test_sparse_set() {
+        let mut set = SparseSet::new(8);
+
+        assert!(set.insert(5));
+        assert!(set.insert(2));
+        assert!(!set.insert(5));
+        assert!(set.insert(7));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![5, 2, 7]);
+
+        set.retain(|value| value != 2);
+
+        assert!(!set.contains(2));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![5, 7]);
+
+        set.clear();
+
+        assert!(set.is_empty());
+        assert!(!set.contains(5));
+    }
+
+    #[test]
+    fn test_program() {
+        let nfa = Nfa::from(parse_regex("(a|b)c").unwrap());
+        let mut slot_map = SlotMap::new(2);
+
+        slot_map.insert(1, &nfa.capture_groups[0]);
+
+        let program = Program::new(&nfa, &slot_map);
+        let group = &nfa.capture_groups[0];
+
+        assert_eq!(program.len(), nfa.state_count);
+        assert_eq!(program.accept, nfa.state_count - 1);
+        assert_eq!(program.slot_count, 4);
+        assert_eq!(program.states[group.start].slots, vec![2]);
+        assert_eq!(program.states[group.end].slots, vec![3]);
+        assert!(program
+            .states
+            .iter()
+            .all(|state| state.transitions.iter().all(Transition::is_consuming)));
+    }
+}
@@ -1,157 +1,145 @@
 use crate::{
+    builder::RegexBuilder,
+    dfa::Dfa,
     error::Error,
-    nfa::{Nfa, StateId, START as INITAL_STATE},
-    parser::parse_regex,
+    lazy_dfa::{CacheExhausted, DfaCache, LazyDfa, CACHE_CAPACITY},
+    nfa::{CaptureKind, Nfa},
+    pikevm::{MatchSemantics, PikeVm, PikeVmCache, SlotMap, Slots},
+    program::Program,
 };
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
+    collections::{BTreeMap, HashMap},
     fmt,
+    sync::{Mutex, MutexGuard, PoisonError},
 };
 
-type Captures = HashMap<usize, Vec<CaptureKind>>;
-
 #[derive(Debug)]
 pub struct Regex {
     pub(crate) nfa: Nfa,
-    start_capture: Captures,
-    end_capture: Captures,
+    semantics: MatchSemantics,
+    program: Program,
+    group_names: Vec<String>,
+    dfa_cache: Mutex<DfaCache>,
+    vm_cache: Mutex<Option<PikeVmCache>>,
+    dfa: Option<Dfa>,
 }
 
 impl<'a> Regex {
     pub fn new(pattern: &str) -> Result<Self, Error> {
-        let ast = parse_regex(pattern)?;
-        let nfa = Nfa::from(ast);
-        let mut start_capture: Captures = HashMap::new();
-        let mut end_capture: Captures = HashMap::new();
-
-        for (index, group) in nfa.capture_groups.iter().enumerate() {
-            start_capture
-                .entry(group.start)
-                .or_default()
-                .push(CaptureKind::Indexed(index));
-            end_capture
-                .entry(group.end)
-                .or_default()
-                .push(CaptureKind::Indexed(index));
-        }
+        RegexBuilder::new(pattern).build()
+    }
 
-        for (name, group) in nfa.named_capture_groups.iter() {
-            start_capture
-                .entry(group.start)
-                .or_default()
-                .push(CaptureKind::Named(name.to_string()));
-            end_capture
-                .entry(group.end)
-                .or_default()
-                .push(CaptureKind::Named(name.to_string()));
+    pub(crate) fn from_nfa(
+        nfa: Nfa,
+        semantics: MatchSemantics,
+        dfa_state_limit: Option<usize>,
+    ) -> Self {
+        let group_names: Vec<_> = nfa.named_capture_groups.keys().cloned().collect();
+        let indexed_count = nfa.capture_groups.len() + 1;
+        let mut slot_map = SlotMap::new(indexed_count + group_names.len());
+
+        for (kind, group) in nfa.all_capture_groups() {
+            let index = match kind {
+                CaptureKind::Indexed(index) => index + 1,
+                CaptureKind::Named(name) => {
+                    let position = group_names.iter().position(|n| *n == name);
+                    indexed_count + position.expect("named group should be registered")
+                }
+            };
+
+            slot_map.insert(index, group);
         }
 
-        Ok(Self {
+        let has_captures = nfa.all_capture_groups().next().is_some();
+        let dfa = dfa_state_limit
+            .filter(|_| !has_captures)
+            .and_then(|limit| Dfa::new(&nfa, semantics, false, limit))
+            .map(Dfa::minimize);
+        let program = Program::new(&nfa, &slot_map);
+
+        Self {
             nfa,
-            start_capture,
-            end_capture,
-        })
+            semantics,
+            program,
+            group_names,
+            dfa_cache: Mutex::new(DfaCache::new(false, CACHE_CAPACITY)),
+            vm_cache: Mutex::new(None),
+            dfa,
+        }
     }
 
     pub fn captures(&self, input: &'a str) -> Option<Capture<'a>> {
-        self.matches(input, false).into_iter().next()
+        self.matches(input, false, true).into_iter().next()
     }
 
     pub fn captures_all(&self, input: &'a str) -> Vec<Capture<'a>> {
-        self.matches(input, true)
+        self.matches(input, true, true)
     }
 
     pub fn find(&self, input: &'a str) -> Option<Match<'a>> {
-        self.matches(input, false)
+        self.matches(input, false, false)
             .into_iter()
             .next()
             .and_then(|mut c| c.captures.remove(&0))
     }
 
     pub fn find_all(&self, input: &'a str) -> Vec<Match<'a>> {
-        self.matches(input, true)
+        self.matches(input, true, false)
             .into_iter()
             .flat_map(|mut captures| captures.captures.remove(&0))
             .collect()
     }
 
-    fn matches(&self, input: &'a str, all: bool) -> Vec<Capture<'a>> {
+    fn matches(&self, input: &'a str, all: bool, with_captures: bool) -> Vec<Capture<'a>> {
         let mut result: Vec<Capture<'_>> = Vec::new();
-
-        for (i, _) in input.char_indices() {
-            let start = match result.last() {
-                Some(capt) => capt.get(0).map_or(i, |m| if i > m.end { i } else { m.end }),
-                None => i,
+        let vm = match with_captures {
+            true => self.pike_vm(),
+            false => PikeVm::new(&self.program, self.semantics, 2),
+        };
+        let mut cache = lock(&self.vm_cache)
+            .take()
+            .filter(|cache| cache.slot_count() == vm.slot_count())
+            .unwrap_or_else(|| vm.cache());
+        let mut use_dfa = true;
+        let mut start = 0;
+
+        while start <= input.len() {
+            let end = match &self.dfa {
+                Some(full_dfa) => full_dfa.find_end(input, start),
+                None if use_dfa => self.lazy_find_end(input, start).unwrap_or_else(|_| {
+                    use_dfa = false;
+                    Some(input.len())
+                }),
+                None => Some(input.len()),
             };
 
-            if i < start {
-                continue;
-            }
-
-            let input_len = input[start..].len();
-            let mut char_count = 0;
-            let mut captures = HashMap::new();
-            let mut named_captures = HashMap::new();
-            let mut states = HashSet::new();
-            let mut end = None;
-            let mut char_index_map = HashMap::new();
-
-            states.insert(INITAL_STATE);
-
-            for (j, ch) in input[start..].char_indices() {
-                char_index_map.insert(j + start, char_count);
-                char_count += 1;
-
-                states = states
-                    .iter()
-                    .flat_map(|&s| self.nfa.epsilon_closure(s))
-                    .collect();
-
-                self.update_captures(&mut captures, &mut named_captures, &states, j + start);
-
-                if self.has_accepting_state(&states) {
-                    end = Some(j + start)
-                }
-
-                states = states
-                    .iter()
-                    .flat_map(|state| self.nfa.next(*state, ch))
-                    .collect();
-
-                if states.is_empty() {
-                    break;
-                }
-            }
-
-            char_index_map.insert(input_len, char_count);
-            states = states
-                .iter()
-                .flat_map(|&s| self.nfa.epsilon_closure(s))
-                .collect();
-
-            self.update_captures(
-                &mut captures,
-                &mut named_captures,
-                &states,
-                input_len + start,
-            );
-
-            if self.has_accepting_state(&states) {
-                end = Some(input_len + start);
-            }
+            let Some(end) = end else {
+                break;
+            };
+            let Some(slots) = vm.search(&mut cache, input, start, end) else {
+                break;
+            };
+            let (Some(match_start), Some(match_end)) = (slots[0], slots[1]) else {
+                break;
+            };
 
-            if end.is_none() {
-                continue;
-            }
+            start = match input[match_end..].chars().next() {
+                _ if match_start < match_end => match_end,
+                Some(ch) => match_end + ch.len_utf8(),
+                None => input.len() + 1,
+            };
 
-            let captures = captures
-                .into_iter()
-                .chain([(0, (Some(start), end))])
-                .flat_map(|(index, (start, end))| Some(index).zip(self.new_mach(input, start, end)))
+            let captures = (0..=self.nfa.capture_groups.len())
+                .flat_map(|index| Some(index).zip(self.new_mach(input, &slots, index)))
                 .collect();
-            let named_captures = named_captures
-                .into_iter()
-                .flat_map(|(name, (start, end))| Some(name).zip(self.new_mach(input, start, end)))
+            let named_captures = self
+                .group_names
+                .iter()
+                .enumerate()
+                .flat_map(|(position, name)| {
+                    let index = self.nfa.capture_groups.len() + 1 + position;
+                    Some(name.to_owned()).zip(self.new_mach(input, &slots, index))
+                })
                 .collect();
 
             let capture = Capture {
@@ -159,113 +147,52 @@ impl<'a> Regex {
                 named_captures,
             };
 
+            result.push(capture);
+
             if !all {
-                return vec![capture];
+                break;
             }
-
-            result.push(capture)
         }
 
+        *lock(&self.vm_cache) = Some(cache);
+
         result
     }
 
     pub fn test(&self, input: &str) -> bool {
-        self.find(input).is_some()
-    }
-
-    fn has_accepting_state(&self, states: &HashSet<StateId>) -> bool {
-        states.iter().any(|s| self.nfa.is_accepting(*s))
-    }
-
-    fn new_mach(
-        &self,
-        input: &'a str,
-        start: Option<usize>,
-        end: Option<usize>,
-    ) -> Option<Match<'a>> {
-        start
-            .zip(end)
-            .map(|(start, end)| Match::new(start, end, &input[start..end]))
-    }
-
-    fn update_captures(
-        &self,
-        captures: &mut HashMap<usize, (Option<usize>, Option<usize>)>,
-        named_captures: &mut HashMap<String, (Option<usize>, Option<usize>)>,
-        states: &HashSet<StateId>,
-        position: usize,
-    ) {
-        for state in states {
-            if let Some(groups) = self.start_capture.get(state) {
-                for group in groups {
-                    self.update_capture_start(captures, named_captures, group, position);
-                }
-            }
-            if let Some(groups) = self.end_capture.get(state) {
-                for group in groups {
-                    self.update_capture_end(captures, named_captures, group, position);
-                }
-            }
+        if let Some(dfa) = &self.dfa {
+            return dfa.is_match(input, 0);
         }
+
+        let is_match =
+            LazyDfa::new(&self.nfa, self.semantics, &mut lock(&self.dfa_cache)).is_match(input, 0);
+
+        is_match.unwrap_or_else(|_| self.pike_vm().is_match(input))
     }
 
-    fn update_capture_start(
-        &self,
-        captures: &mut HashMap<usize, (Option<usize>, Option<usize>)>,
-        named_captures: &mut HashMap<String, (Option<usize>, Option<usize>)>,
-        group: &CaptureKind,
-        position: usize,
-    ) {
-        match group {
-            CaptureKind::Indexed(index) => {
-                captures
-                    .entry(*index + 1)
-                    .and_modify(|(start, end)| {
-                        if end.is_none() {
-                            *start = Some(position)
-                        }
-                    })
-                    .or_insert((Some(position), None));
-            }
-            CaptureKind::Named(name) => {
-                named_captures
-                    .entry(name.to_owned())
-                    .and_modify(|(start, end)| {
-                        if end.is_none() {
-                            *start = Some(position)
-                        }
-                    })
-                    .or_insert((Some(position), None));
-            }
-        }
+    fn lazy_find_end(&self, input: &str, start: usize) -> Result<Option<usize>, CacheExhausted> {
+        LazyDfa::new(&self.nfa, self.semantics, &mut lock(&self.dfa_cache)).find_end(input, start)
     }
 
-    fn update_capture_end(
-        &self,
-        captures: &mut HashMap<usize, (Option<usize>, Option<usize>)>,
-        named_captures: &mut HashMap<String, (Option<usize>, Option<usize>)>,
-        group: &CaptureKind,
-        position: usize,
-    ) {
-        match group {
-            CaptureKind::Indexed(index) => {
-                captures
-                    .entry(*index + 1)
-                    .and_modify(|(_, end)| *end = Some(position));
-            }
-            CaptureKind::Named(name) => {
-                named_captures
-                    .entry(name.to_owned())
-                    .and_modify(|(_, end)| *end = Some(position));
+    fn pike_vm(&self) -> PikeVm<'_> {
+        PikeVm::new(&self.program, self.semantics, self.program.slot_count)
+    }
+
+    fn new_mach(&self, input: &'a str, slots: &Slots, index: usize) -> Option<Match<'a>> {
+        let start = slots.get(index * 2).copied().flatten();
+        let end = slots.get(index * 2 + 1).copied().flatten();
+
+        match (start, end) {
+            (Some(start), Some(end)) if start <= end => {
+                Some(Match::new(start, end, &input[start..end]))
             }
+            _ => None,
         }
     }
 }
 
-#[derive(Debug)]
-enum CaptureKind {
-    Indexed(usize),
-    Named(String),
+fn lock<T>(cache: &Mutex<T>) -> MutexGuard<'_, T> {
+    cache.lock().unwrap_or_else(PoisonError::into_inner)
 }
 
 #[derive(Debug, PartialEq)]
@@ -354,11 +281,12 @@ impl<'a> Match<'a> {
 
 #[cfg(test)]
 mod test {
-    // use std::collections::HashMap;
+    use std::collections::HashMap;
 
     use crate::{
+        error::Error,
         regex::{Match, Regex},
-        // Capture,
+        Capture, MatchSemantics, RegexBuilder,
     };
 
     #[test]
@@ -428,6 +356,145 @@ mod test {
         assert!(!re.test("hey!"));
     }
 
+    #[test]
+    fn test_range_quantifier_lower_bound() {
+        let re = Regex::new("^e{0,2}$").unwrap();
+
+        assert!(re.test(""));
+        assert!(re.test("ee"));
+        assert!(!re.test("eee"));
+
+        let re = Regex::new("^e{1,}$").unwrap();
+
+        assert!(re.test("eee"));
+        assert!(!re.test(""));
+
+        let re = Regex::new("^e{0,}$").unwrap();
+
+        assert!(re.test(""));
+        assert!(re.test("eee"));
+    }
+
+    #[test]
+    fn test_lazy_quantifiers() {
+        let re = Regex::new("<.+?>").unwrap();
+
+        assert_eq!(re.find("<a><b>"), Some(Match::new(0, 3, "<a>")));
+        assert_eq!(
+            re.find_all("<a><b>"),
+            vec![Match::new(0, 3, "<a>"), Match::new(3, 6, "<b>")]
+        );
+
+        let re = Regex::new(r#""(.*?)""#).unwrap();
+        let captures = re.captures(r#"key = "foo", "bar""#).unwrap();
+
+        assert_eq!(captures.get(0), Some(&Match::new(6, 11, r#""foo""#)));
+        assert_eq!(captures.get(1), Some(&Match::new(7, 10, "foo")));
+
+        let re = Regex::new("a??b").unwrap();
+
+        assert_eq!(re.find("ab"), Some(Match::new(0, 2, "ab")));
+
+        let re = Regex::new("a{2,4}?").unwrap();
+
+        assert_eq!(re.find("aaaa"), Some(Match::new(0, 2, "aa")));
+
+        let re = Regex::new("a*?").unwrap();
+
+        assert_eq!(re.find("aaa"), Some(Match::new(0, 0, "")));
+    }
+
+    #[test]
+    fn test_empty_iteration() {
+        for full_dfa in [false, true] {
+            let re = RegexBuilder::new(r"[ab](?:.*?\b)*")
+                .full_dfa(full_dfa)
+                .build()
+                .unwrap();
+
+            assert_eq!(re.find("bbb cb"), Some(Match::new(0, 3, "bbb")));
+        }
+
+        let re = Regex::new(r"(?:\b| |b)*").unwrap();
+
+        assert_eq!(re.find("b"), Some(Match::new(0, 0, "")));
+
+        let re = Regex::new("(?:.*?)*").unwrap();
+
+        assert_eq!(re.find("bb"), Some(Match::new(0, 0, "")));
+    }
+
+    #[test]
+    fn test_match_semantics() {
+        let re = Regex::new("(a|ab)(c|bcd)?").unwrap();
+
+        assert_eq!(re.find("abcd"), Some(Match::new(0, 4, "abcd")));
+        assert_eq!(re.find("abd"), Some(Match::new(0, 1, "a")));
+
+        let re = RegexBuilder::new("(a|ab)(c|bcd)?")
+            .match_semantics(MatchSemantics::LeftmostLongest)
+            .build()
+            .unwrap();
+
+        assert_eq!(re.find("abd"), Some(Match::new(0, 2, "ab")));
+
+        let re = RegexBuilder::new("<.+?>")
+            .match_semantics(MatchSemantics::LeftmostLongest)
+            .build()
+            .unwrap();
+
+        assert_eq!(re.find("<a><b>"), Some(Match::new(0, 6, "<a><b>")));
+    }
+
+    #[test]
+    fn test_unanchored_search() {
+        let input = format!("{}b", "a".repeat(50_000));
+        let re = Regex::new("(a)+b").unwrap();
+
+        assert_eq!(re.find_all(&input).len(), 1);
+        assert!(re.find_all(&input[..50_000]).is_empty());
+
+        let re = Regex::new("a*").unwrap();
+
+        assert_eq!(
+            re.find_all("aab"),
+            vec![
+                Match::new(0, 2, "aa"),
+                Match::new(2, 2, ""),
+                Match::new(3, 3, "")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_full_dfa() {
+        let re = RegexBuilder::new(r"\b[a-z]+ing\b")
+            .full_dfa(true)
+            .build()
+            .unwrap();
+
+        assert!(re.dfa.is_some());
+        assert!(re.test("a running dog"));
+        assert!(!re.test("ringside"));
+        assert_eq!(
+            re.find_all("sing, singing"),
+            vec![Match::new(0, 4, "sing"), Match::new(6, 13, "singing")]
+        );
+
+        let re = RegexBuilder::new("(a|b)*a(a|b){8}")
+            .full_dfa(true)
+            .dfa_state_limit(64)
+            .build()
+            .unwrap();
+
+        assert!(re.dfa.is_none());
+        assert!(re.test("abbbbbbbbb"));
+
+        let re = RegexBuilder::new("(a)+").full_dfa(true).build().unwrap();
+
+        assert!(re.dfa.is_none());
+    }
+
     #[test]
     fn test_character_class() {
         let re = Regex::new(r#"[0-9]+(\.[0-9]+)?"#).unwrap();
@@ -437,9 +504,365 @@ mod test {
         assert!(!re.test(""));
     }
 
+    #[test]
+    fn test_anchors() {
+        let re = Regex::new("^foo$").unwrap();
+
+        assert!(re.test("foo"));
+        assert!(!re.test("xfoo"));
+        assert!(!re.test("foox"));
+
+        let re = Regex::new("^$").unwrap();
+
+        assert!(re.test(""));
+        assert!(!re.test("a"));
+
+        let re = Regex::new("(^a|b$)").unwrap();
+        let matches = re.find_all("aab");
+
+        assert_eq!(matches, vec![Match::new(0, 1, "a"), Match::new(2, 3, "b")]);
+    }
+
+    #[test]
+    fn test_multiline() {
+        let input = "foo\nbar\nfoo";
+        let re = Regex::new("^foo$").unwrap();
+
+        assert!(re.find_all(input).is_empty());
+
+        let re = RegexBuilder::new("^foo$").multiline(true).build().unwrap();
+        let expected = vec![Match::new(0, 3, "foo"), Match::new(8, 11, "foo")];
+
+        assert_eq!(re.find_all(input), expected);
+
+        let re = Regex::new("(?m)^(foo|bar)$").unwrap();
+
+        assert_eq!(re.find_all(input).len(), 3);
+
+        let re = RegexBuilder::new("(?-m)^foo")
+            .multiline(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(re.find_all(input), vec![Match::new(0, 3, "foo")]);
+    }
+
+    #[test]
+    fn test_dot_matches_newline() {
+        let re = Regex::new("a.+b").unwrap();
+
+        assert!(!re.test("a\nb"));
+        assert_eq!(re.find("a\na-xb"), Some(Match::new(2, 6, "a-xb")));
+
+        let re = Regex::new("(?s)a.+b").unwrap();
+
+        assert_eq!(re.find("a\na-xb"), Some(Match::new(0, 6, "a\na-xb")));
+
+        let re = RegexBuilder::new("a.b")
+            .dot_matches_newline(true)
+            .build()
+            .unwrap();
+
+        assert!(re.test("a\nb"));
+    }
+
+    #[test]
+    fn test_extended_mode() {
+        let re = Regex::new(
+            r"(?x)
+            (?<year>\d{4}) - # year
+            (?<month>\d{2})  # month
+            \ [ ]?\#        # literal space, optional space and hash
+            ",
+        )
+        .unwrap();
+        let captures = re.captures("on 2024-01 # ok").unwrap();
+
+        assert_eq!(captures.get_name("year"), Some(&Match::new(3, 7, "2024")));
+        assert_eq!(captures.get_name("month"), Some(&Match::new(8, 10, "01")));
+        assert!(!re.test("2024-01#"));
+    }
+
+    #[test]
+    fn test_empty_alternative() {
+        let re = Regex::new("^(?:a||b)$").unwrap();
+
+        assert!(re.test(""));
+        assert!(re.test("b"));
+        assert!(!re.test("|"));
+
+        let re = Regex::new("a||b").unwrap();
+
+        assert_eq!(re.find("|"), Some(Match::new(0, 0, "")));
+        assert_eq!(re.find(""), Some(Match::new(0, 0, "")));
+    }
+
+    #[test]
+    fn test_long_literal() {
+        let literal: String = ('\u{4E00}'..).take(10_000).collect();
+        let re = Regex::new(&literal).unwrap();
+
+        assert!(re.test(&format!("ab{literal}")));
+        assert!(!re.test(&literal[3..]));
+        assert!(Regex::new(&"ab".repeat(5000)).is_ok());
+    }
+
+    #[test]
+    fn test_long_class() {
+        let re = Regex::new(&format!("[{}]", "ab".repeat(50_000))).unwrap();
+
+        assert!(re.test("b"));
+        assert!(!re.test("c"));
+    }
+
+    #[test]
+    fn test_builder_options() {
+        let re = RegexBuilder::new("a.b")
+            .dot_matches_newline(false)
+            .build()
+            .unwrap();
+
+        assert!(re.test("a-b"));
+        assert!(!re.test("a\nb"));
+
+        let re = RegexBuilder::new("a b+ # trailing comment\n | c")
+            .ignore_whitespace(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(re.find("xabbb"), Some(Match::new(1, 5, "abbb")));
+        assert!(re.test("c"));
+        assert!(!re.test("a b"));
+
+        let result = RegexBuilder::new("(ab){100}").size_limit(100).build();
+
+        assert!(matches!(result, Err(Error::NfaSizeLimitExceeded(100))));
+
+        let result = RegexBuilder::new("((a))").nest_limit(1).build();
+
+        assert!(matches!(result, Err(Error::ParsingError(_))));
+        assert!(RegexBuilder::new("((a))").nest_limit(2).build().is_ok());
+    }
+
+    #[test]
+    fn test_unicode_properties() {
+        let re = Regex::new(r"\p{Greek}+").unwrap();
+
+        assert_eq!(re.find("abc αβγ"), Some(Match::new(4, 10, "αβγ")));
+
+        let re = Regex::new(r"\p{Lu}\p{Ll}+ \P{L}+").unwrap();
+
+        assert!(re.test("Élan 42"));
+        assert!(!re.test("élan 42"));
+        assert!(!re.test("Élan x"));
+
+        let re = Regex::new(r"[\p{Hiragana}\p{Nd}-]+").unwrap();
+
+        assert_eq!(re.find("ab ここ-٣ cd"), Some(Match::new(3, 12, "ここ-٣")));
+
+        let re = Regex::new(r"(?i)\p{Lu}").unwrap();
+
+        assert!(re.test("a"));
+        assert!(!re.test("1"));
+    }
+
+    #[test]
+    fn test_unicode_perl_classes() {
+        let re = Regex::new(r"\w+").unwrap();
+
+        assert_eq!(
+            re.find("-- crème_brûlée --"),
+            Some(Match::new(3, 18, "crème_brûlée"))
+        );
+
+        let re = Regex::new(r"\d+\s\S").unwrap();
+
+        assert!(re.test("١٢٣\u{A0}x"));
+        assert!(!re.test("12\u{A0} "));
+
+        let re = Regex::new(r"\bé\w*\b").unwrap();
+
+        assert_eq!(re.find("café étage"), Some(Match::new(6, 12, "étage")));
+
+        let re = RegexBuilder::new(r"\bé\w*\b")
+            .full_dfa(true)
+            .build()
+            .unwrap();
+
+        assert!(re.test("un été"));
+        assert!(!re.test("café"));
+
+        let re = RegexBuilder::new(r"\w+").unicode(false).build().unwrap();
+
+        assert_eq!(re.find("crème_brûlée"), Some(Match::new(0, 2, "cr")));
+
+        let re = Regex::new(r"(?-u)\bé").unwrap();
+
+        assert!(re.test("café"));
+        assert!(!re.test(" é"));
+    }
+
+    #[test]
+    fn test_escape_sequences() {
+        let re = Regex::new(r"(\w+)\t\|\x20\u{1F600}\$").unwrap();
+        let captures = re.captures("x name\t| 😀$").unwrap();
+
+        assert_eq!(captures.get(1), Some(&Match::new(2, 6, "name")));
+        assert!(!re.test("name\t|😀$"));
+    }
+
+    #[test]
+    fn test_nested_shorthands() {
+        let re = Regex::new(r"[\w-]+").unwrap();
+
+        assert_eq!(re.find("(foo-bar_1)"), Some(Match::new(1, 10, "foo-bar_1")));
+
+        let re = Regex::new(r"^[^\d\s]+$").unwrap();
+
+        assert!(re.test("abc_é"));
+        assert!(!re.test("ab c"));
+        assert!(!re.test("ab٣"));
+
+        let re = Regex::new(r"^[\D\p{Nd}]+$").unwrap();
+
+        assert!(re.test("any 123 text"));
+
+        let re = Regex::new(r"(?i)^[\p{Greek}\d]+$").unwrap();
+
+        assert!(re.test("ΑΒγ12"));
+        assert!(!re.test("ab"));
+    }
+
+    #[test]
+    fn test_class_set_operations() {
+        let re = Regex::new(r"^[\p{L}&&[^a-z]]+$").unwrap();
+
+        assert!(re.test("ÀΩX"));
+        assert!(!re.test("ÀxΩ"));
+
+        let re = Regex::new(r"[\w--\d]+").unwrap();
+
+        assert_eq!(re.find("123abc_4"), Some(Match::new(3, 7, "abc_")));
+
+        let re = Regex::new(r"^[a-z--[aeiou]]+$").unwrap();
+
+        assert!(re.test("rhythm"));
+        assert!(!re.test("rhyme"));
+
+        let re = Regex::new(r"^[[0-9]x[^\s\w]]+$").unwrap();
+
+        assert!(re.test("1x!2"));
+        assert!(!re.test("1y"));
+
+        let re = Regex::new(r"(?i)[\p{Greek}--α]").unwrap();
+
+        assert!(re.test("β"));
+        assert!(!re.test("Α"));
+
+        let re = Regex::new(r"(?i)[\w--[ab]]").unwrap();
+
+        assert!(re.test("C"));
+        assert!(!re.test("a"));
+        assert!(!re.test("B"));
+
+        let re = Regex::new(r"(?i)^[a-z&&[^K]]+$").unwrap();
+
+        assert!(re.test("aBc"));
+        assert!(!re.test("k"));
+    }
+
+    #[test]
+    fn test_posix_classes() {
+        let re =
+            Regex::new(r"[[:alpha:]_][[:alnum:]_]*[[:space:]]*=[[:blank:]]*[[:xdigit:]]+").unwrap();
+
+        assert_eq!(
+            re.find("let _x1 =\tBEEF;"),
+            Some(Match::new(4, 14, "_x1 =\tBEEF"))
+        );
+        assert!(!re.test("1 = ff"));
+
+        let re = Regex::new(r"^[[:^alpha:][:upper:]]+$").unwrap();
+
+        assert!(re.test("ABC 123"));
+        assert!(!re.test("ABc"));
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let re = Regex::new("(?i)hello [a-f]+").unwrap();
+
+        assert!(re.test("HeLLo BEEF"));
+        assert!(!re.test("HeLLo G"));
+
+        let re = Regex::new("a(?i:b)c").unwrap();
+
+        assert!(re.test("aBc"));
+        assert!(!re.test("aBC"));
+
+        let re = RegexBuilder::new("k[^x]")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+
+        assert!(re.test("\u{212A}y"));
+        assert!(!re.test("kX"));
+
+        let re = RegexBuilder::new("(?-i)a")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+
+        assert!(!re.test("A"));
+        assert!(Regex::new("(?i)σ").unwrap().test("ς"));
+    }
+
+    #[test]
+    fn test_case_insensitive_negation() {
+        for pattern in [
+            r"(?i)[[^a]]",
+            r"(?i)\P{Ll}",
+            r"(?i)[[:^lower:]]",
+            r"(?i)[^a]",
+        ] {
+            let re = Regex::new(pattern).unwrap();
+
+            assert!(!re.test("a"), "{pattern}");
+            assert!(!re.test("A"), "{pattern}");
+        }
+
+        let re = Regex::new(r"(?i)[[^a]b]").unwrap();
+
+        assert!(re.test("B"));
+        assert!(!re.test("A"));
+        assert!(Regex::new(r"(?i)[^a]").unwrap().test("b"));
+        assert!(Regex::new(r"(?i)\p{Ll}").unwrap().test("A"));
+    }
+
+    #[test]
+    fn test_word_boundary() {
+        let re = Regex::new(r#"\bfoo\b"#).unwrap();
+        let matches = re.find_all("foo foobar barfoo (foo)");
+
+        assert_eq!(
+            matches,
+            vec![Match::new(0, 3, "foo"), Match::new(19, 22, "foo")]
+        );
+
+        let re = Regex::new(r#"\Boo\B"#).unwrap();
+
+        assert!(re.test("food"));
+        assert!(!re.test("foo"));
+
+        let re = Regex::new(r#"\b(?<id>\w+)\b = 1"#).unwrap();
+        let captures = re.captures("let value = 1").unwrap();
+
+        assert_eq!(captures.get_name("id"), Some(&Match::new(4, 9, "value")));
+    }
+
     #[test]
     fn test_capture_groups() {
-        let regex = Regex::new(r#"(ah+)(:?eh+)(oh+)"#).unwrap();
+        let regex = Regex::new(r#"(ah+)(?:eh+)(oh+)"#).unwrap();
         let matches = regex.captures("ahhhhehhhohhh").unwrap();
 
         assert_eq!(matches.get(0), Some(&Match::new(0, 13, "ahhhhehhhohhh")));
@@ -447,6 +870,42 @@ mod test {
         assert_eq!(matches.get(2), Some(&Match::new(9, 13, "ohhh")));
     }
 
+    #[test]
+    fn test_group_syntax() {
+        let regex = Regex::new(r#"(?P<key>\w+)=(?'value'\d+)(?i:PX)?"#).unwrap();
+        let matches = regex.captures("width=42px").unwrap();
+
+        assert_eq!(matches.get_name("key"), Some(&Match::new(0, 5, "width")));
+        assert_eq!(matches.get_name("value"), Some(&Match::new(6, 8, "42")));
+        assert_eq!(matches.get(0), Some(&Match::new(0, 10, "width=42px")));
+
+        let regex = RegexBuilder::new("(a)(:?b)")
+            .legacy_non_capturing(true)
+            .build()
+            .unwrap();
+        let matches = regex.captures("ab").unwrap();
+
+        assert_eq!(matches.get(2), None);
+        assert!(Regex::new("(a)(:?b)").unwrap().test("a:b"));
+
+        for pattern in ["a(?=b)", "a(?!b)", "(?<=a)b", "(?<!a)b"] {
+            assert!(Regex::new(pattern).is_err());
+        }
+    }
+
+    #[test]
+    fn test_identifier_capture_names() {
+        let regex = Regex::new(r"(?<year_4>\d{4})-(?<g1>\d+)").unwrap();
+        let matches = regex.captures("2024-07").unwrap();
+
+        assert_eq!(matches.get_name("year_4"), Some(&Match::new(0, 4, "2024")));
+        assert_eq!(matches.get_name("g1"), Some(&Match::new(5, 7, "07")));
+        assert!(matches!(
+            Regex::new(r"(?<field_12>a)(?<field_12>b)"),
+            Err(Error::ParsingError(_))
+        ));
+    }
+
     #[test]
     fn test_named_capture_groups() {
         let regex = Regex::new(r#"(?<hour>\d+):(?<minute>\d+)"#).unwrap();
@@ -457,23 +916,70 @@ mod test {
         assert_eq!(matches.get_name("minute"), Some(&Match::new(3, 5, "30")));
     }
 
-    // #[test]
-    // fn test_repeated_group() {
-    //     let regex = Regex::new(r#"(hi)+(ah)+"#).unwrap();
-    //     let matches = regex.captures("hihiah").unwrap();
-    //     let expected = Capture {
-    //         captures: vec![
-    //             (0, Match::new(0, 6, "hihiah")),
-    //             (1, Match::new(2, 4, "hi")),
-    //             (2, Match::new(4, 6, "ah")),
-    //         ]
-    //         .into_iter()
-    //         .collect(),
-    //         named_captures: HashMap::new(),
-    //     };
-    //
-    //     assert_eq!(matches, expected);
-    // }
+    #[test]
+    fn test_repeated_group() {
+        let regex = Regex::new(r#"(hi)+(ah)+"#).unwrap();
+        let matches = regex.captures("hihiah").unwrap();
+        let expected = Capture {
+            captures: vec![
+                (0, Match::new(0, 6, "hihiah")),
+                (1, Match::new(2, 4, "hi")),
+                (2, Match::new(4, 6, "ah")),
+            ]
+            .into_iter()
+            .collect(),
+            named_captures: HashMap::new(),
+        };
+
+        assert_eq!(matches, expected);
+    }
+
+    #[test]
+    fn test_zero_repetition_group() {
+        for pattern in ["(a){0}(b)", "(a){0,0}(b)"] {
+            let regex = Regex::new(pattern).unwrap();
+            let matches = regex.captures("ab").unwrap();
+
+            assert_eq!(matches.get(0), Some(&Match::new(1, 2, "b")));
+            assert_eq!(matches.get(1), None);
+            assert_eq!(matches.get(2), Some(&Match::new(1, 2, "b")));
+        }
+
+        let matches = Regex::new("(?<skip>a){0}b")
+            .unwrap()
+            .captures("ab")
+            .unwrap();
+
+        assert_eq!(matches.get_name("skip"), None);
+    }
+
+    #[test]
+    fn test_quantified_groups() {
+        let regex = Regex::new(r#"(a|(b))+"#).unwrap();
+        let matches = regex.captures("ba").unwrap();
+
+        assert_eq!(matches.get(1), Some(&Match::new(1, 2, "a")));
+        assert_eq!(matches.get(2), Some(&Match::new(0, 1, "b")));
+
+        let regex = Regex::new(r#"x(y)?z"#).unwrap();
+        let matches = regex.captures("xz").unwrap();
+
+        assert_eq!(matches.get(0), Some(&Match::new(0, 2, "xz")));
+        assert_eq!(matches.get(1), None);
+
+        let regex = Regex::new(r#"(?<digit>\d){2,3}(x)"#).unwrap();
+        let matches = regex.captures("12x").unwrap();
+
+        assert_eq!(matches.get_name("digit"), Some(&Match::new(1, 2, "2")));
+        assert_eq!(matches.get(1), Some(&Match::new(2, 3, "x")));
+
+        let regex = Regex::new(r#"(a)(b){2}(c)"#).unwrap();
+        let matches = regex.captures("abbc").unwrap();
+
+        assert_eq!(matches.get(2), Some(&Match::new(2, 3, "b")));
+        assert_eq!(matches.get(3), Some(&Match::new(3, 4, "c")));
+        assert_eq!(matches.get(4), None);
+    }
 
     #[test]
     fn test_find() {
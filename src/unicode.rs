@@ -0,0 +1,102 @@
+use crate::{
+    interval_set::IntervalSet,
+    unicode_tables::{CASE_FOLDING, GENERAL_CATEGORIES, SCRIPTS},
+};
+
+type PropertyTable = &'static [(&'static str, &'static [(char, char)])];
+
+pub fn case_variants(ch: char) -> &'static [char] {
+    CASE_FOLDING
+        .binary_search_by_key(&ch, |&(key, _)| key)
+        .map_or(&[], |index| CASE_FOLDING[index].1)
+}
+
+pub fn fold_set(set: &IntervalSet) -> IntervalSet {
+    let variants = CASE_FOLDING
+        .iter()
+        .filter(|&&(key, _)| set.contains(key))
+        .flat_map(|(_, variants)| variants.iter().map(|&ch| (ch, ch)));
+
+    IntervalSet::new(set.ranges().iter().copied().chain(variants))
+}
+
+pub fn in_ranges(ranges: &[(char, char)], ch: char) -> bool {
+    let index = ranges.partition_point(|&(_, upper)| upper < ch);
+    ranges.get(index).is_some_and(|&(lower, _)| lower <= ch)
+}
+
+pub fn lookup_property(name: &str) -> Option<(&'static str, &'static [(char, char)])> {
+    let name: String = name
+        .chars()
+        .filter(|&ch| !matches!(ch, '_' | '-') && !ch.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    match name.split_once('=') {
+        Some(("gc" | "generalcategory", value)) => find_property(GENERAL_CATEGORIES, value),
+        Some(("sc" | "script", value)) => find_property(SCRIPTS, value),
+        Some(_) => None,
+        None => find_property(GENERAL_CATEGORIES, &name).or_else(|| find_property(SCRIPTS, &name)),
+    }
+}
+
+fn find_property(
+    table: PropertyTable,
+    name: &str,
+) -> Option<(&'static str, &'static [(char, char)])> {
+    table
+        .binary_search_by_key(&name, |&(key, _)| key)
+        .ok()
+        .map(|index| table[index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_variants() {
+        assert_eq!(case_variants('a'), &['A']);
+        assert_eq!(case_variants('k'), &['K', '\u{212A}']);
+        assert_eq!(case_variants('ς'), &['Σ', 'σ']);
+        assert!(case_variants('1').is_empty());
+        assert!(case_variants('\u{131}').is_empty());
+    }
+
+    #[test]
+    fn test_fold_set() {
+        let folded = fold_set(&IntervalSet::new([('a', 'c'), ('K', 'K')]));
+
+        assert_eq!(
+            folded.ranges(),
+            &[
+                ('A', 'C'),
+                ('K', 'K'),
+                ('a', 'c'),
+                ('k', 'k'),
+                ('\u{212A}', '\u{212A}')
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lookup_property() {
+        let (name, ranges) = lookup_property("Lu").unwrap();
+
+        assert_eq!(name, "lu");
+        assert!(ranges.contains(&('A', 'Z')));
+        assert_eq!(lookup_property("Uppercase_Letter").unwrap().1, ranges);
+        assert_eq!(lookup_property("gc = lu"), Some((name, ranges)));
+        assert_eq!(
+            lookup_property("Script=Greek").unwrap().1,
+            lookup_property("grek").unwrap().1
+        );
+        assert!(lookup_property("sc=Lu").is_none());
+        assert!(lookup_property("Klingon").is_none());
+
+        let (_, letters) = lookup_property("L").unwrap();
+
+        assert!(letters.len() > ranges.len());
+        assert!(letters.windows(2).all(|pair| pair[0].1 < pair[1].0));
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     nfa::{StateId, TransitionKind},
     regex::{Capture, Regex},
-    Match,
+    Match, RegexBuilder,
 };
 use std::collections::HashMap;
 use wasm_bindgen::prelude::*;
@@ -50,6 +50,38 @@ impl RegexEngine {
     }
 }
 
+#[wasm_bindgen(js_name = "parseDiagnostics")]
+pub fn parse_diagnostics(pattern: &str) -> Vec<RegexDiagnostic> {
+    RegexBuilder::new(pattern)
+        .diagnostics()
+        .into_iter()
+        .map(|error| {
+            let span = error.char_span(pattern);
+
+            RegexDiagnostic {
+                message: error.kind.to_string(),
+                start: span.start,
+                end: span.end,
+            }
+        })
+        .collect()
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegexDiagnostic {
+    message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[wasm_bindgen]
+impl RegexDiagnostic {
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
 #[wasm_bindgen]
 #[derive(Debug, Clone, PartialEq)]
 pub struct RegexGroup {
@@ -127,7 +159,7 @@ fn get_char_index(input: &str) -> HashMap<usize, usize> {
 
 #[cfg(test)]
 mod tests {
-    use super::{RegexCapture, RegexEngine, RegexGroup};
+    use super::{parse_diagnostics, RegexCapture, RegexDiagnostic, RegexEngine, RegexGroup};
 
     #[test]
     fn test_unicode_range() {
@@ -152,4 +184,22 @@ mod tests {
 
         assert_eq!(matches, expected);
     }
+
+    #[test]
+    fn test_parse_diagnostics() {
+        let expected = vec![
+            RegexDiagnostic {
+                message: "Invalid escape sequence".to_string(),
+                start: 1,
+                end: 3,
+            },
+            RegexDiagnostic {
+                message: "Missing )".to_string(),
+                start: 3,
+                end: 4,
+            },
+        ];
+
+        assert_eq!(parse_diagnostics(r#"こ\q(a"#), expected);
+    }
 }
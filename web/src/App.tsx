@@ -3,7 +3,12 @@ import { Viz, instance } from "@viz-js/viz";
 import Navbar from "./components/Navbar";
 import ExpressionsPopup from "./components/ExpressionsPopup";
 import { RiQuestionFill } from "react-icons/ri";
-import { RegexEngine, RegexCapture } from "regex-potata";
+import {
+  RegexEngine,
+  RegexCapture,
+  RegexDiagnostic,
+  parseDiagnostics,
+} from "regex-potata";
 import { graphFromRegex } from "./utils/graphiz";
 import TestInput from "./components/TestInput";
 import Footer from "./components/Footer";
@@ -20,6 +25,7 @@ const App = () => {
   const [isPopupOpen, setIsPopupOpen] = useState(false);
   const [svg, setSvg] = useState<SVGSVGElement>();
   const [captures, setCaptures] = useState<RegexCapture[]>([]);
+  const [diagnostics, setDiagnostics] = useState<RegexDiagnostic[]>([]);
   const vizInstance = useRef<Viz>();
 
   useEffect(() => {
@@ -33,6 +39,8 @@ const App = () => {
   }, []);
 
   useEffect(() => {
+    setDiagnostics(parseDiagnostics(regexInput));
+
     try {
       setRegexInstance(new RegexEngine(regexInput));
     } catch (error) {
@@ -83,6 +91,7 @@ const App = () => {
             <RegexInput
               value={regexInput}
               error={!regexInstance}
+              diagnostics={diagnostics}
               onInput={(v) => setRegexInput(v)}
             />
           </div>
@@ -2,15 +2,20 @@ import { copy } from "clipboard";
 import { RiCheckFill, RiCloseCircleFill, RiFileCopyLine } from "react-icons/ri";
 import ToolTip from "./ToolTip";
 import { useState } from "react";
+import { RegexDiagnostic } from "regex-potata";
 
 type InputProps = {
   value: string;
   error: boolean;
+  diagnostics: RegexDiagnostic[];
   onInput: (value: string) => void;
 };
 
-const RegexInput = ({ value, error, onInput }: InputProps) => {
+const RegexInput = ({ value, error, diagnostics, onInput }: InputProps) => {
   const [hasCopied, setHasCopied] = useState(false);
+  const chars = Array.from(value);
+  const slice = (start: number, end?: number) =>
+    chars.slice(start, end).join("");
 
   const handleCopy = () => {
     setHasCopied(true);
@@ -36,7 +41,7 @@ const RegexInput = ({ value, error, onInput }: InputProps) => {
           </ToolTip>
         </div>
       </div>
-      {error && (
+      {error && diagnostics.length === 0 && (
         <div
           className="flex items-center space-x-3
           font-semibold text-red-400"
@@ -45,6 +50,29 @@ const RegexInput = ({ value, error, onInput }: InputProps) => {
           <span>Invalid Regular expression</span>
         </div>
       )}
+      {diagnostics.map((diagnostic, index) => {
+        const end = Math.max(diagnostic.end, diagnostic.start + 1);
+
+        return (
+          <div
+            key={index}
+            className="flex items-center space-x-3
+            font-semibold text-red-400"
+          >
+            <RiCloseCircleFill />
+            <span>
+              {diagnostic.message()} at position {diagnostic.start + 1}
+            </span>
+            <code className="text-slate-400">
+              {slice(0, diagnostic.start)}
+              <span className="underline decoration-wavy decoration-red-400">
+                {slice(diagnostic.start, end)}
+              </span>
+              {slice(end)}
+            </code>
+          </div>
+        );
+      })}
     </>
   );
 };